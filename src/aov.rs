@@ -0,0 +1,113 @@
+use std::str::FromStr;
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::scene::Sample;
+use crate::vec3::Vec3;
+
+/// Arbitrary output variable, auxiliary per-pixel data rendered alongside the image
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Aov {
+    /// Distance from the camera to the first hit along the camera view direction
+    Depth,
+    /// World-space surface normal of the first hit
+    Normal,
+    /// Surface normal of the first hit in camera right, up and forward axes
+    CameraNormal,
+    /// Surface color of the first hit
+    Albedo,
+    /// World-space position of the first hit
+    Position,
+    /// Index of the first hit object in the scene plus one, zero if nothing is hit
+    ObjectId,
+    /// Light seen directly by the camera, such as light sources and the sky
+    Emission,
+    /// Light reaching the camera after a single reflection
+    Direct,
+    /// Light reaching the camera after multiple reflections
+    Indirect,
+}
+
+impl Aov {
+    pub const ALL: [Aov; 9] = [
+        Aov::Depth,
+        Aov::Normal,
+        Aov::CameraNormal,
+        Aov::Albedo,
+        Aov::Position,
+        Aov::ObjectId,
+        Aov::Emission,
+        Aov::Direct,
+        Aov::Indirect,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Aov::Depth => "depth",
+            Aov::Normal => "normal",
+            Aov::CameraNormal => "camera_normal",
+            Aov::Albedo => "albedo",
+            Aov::Position => "position",
+            Aov::ObjectId => "object_id",
+            Aov::Emission => "emission",
+            Aov::Direct => "direct",
+            Aov::Indirect => "indirect",
+        }
+    }
+
+    /// Whether the pixel value is an average of all samples
+    /// Otherwise the value of the first pixel sample is kept, since ids can't be blended
+    pub fn averaged(&self) -> bool {
+        *self != Aov::ObjectId
+    }
+
+    /// AOV value of a single sample
+    pub fn value(&self, sample: &Sample, camera: &Camera) -> Color {
+        let vec = |v: Vec3| Color::rgb(v.x, v.y, v.z);
+        match (self, &sample.hit) {
+            (Aov::Emission, _) => sample.emission,
+            (Aov::Direct, _) => sample.direct,
+            (Aov::Indirect, _) => sample.indirect,
+            (_, None) => Color::BLACK,
+            (Aov::Depth, Some(hit)) => {
+                Color::mono(hit.dist * hit.dir.cos_angle(&camera.viewport.dir))
+            }
+            (Aov::Normal, Some(hit)) => vec(hit.normal),
+            (Aov::CameraNormal, Some(hit)) => {
+                let (right, up, forward) = camera.axes();
+                Color::rgb(
+                    hit.normal.dot(&right),
+                    hit.normal.dot(&up),
+                    hit.normal.dot(&forward),
+                )
+            }
+            (Aov::Albedo, Some(hit)) => hit.albedo,
+            (Aov::Position, Some(hit)) => vec(hit.point),
+            (Aov::ObjectId, Some(hit)) => Color::mono((hit.object + 1) as f32),
+        }
+    }
+}
+
+impl FromStr for Aov {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Aov::ALL
+            .into_iter()
+            .find(|a| a.name() == s)
+            .ok_or_else(|| format!("unknown AOV `{s}`"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::aov::Aov;
+
+    #[test]
+    fn parse_names() {
+        for aov in Aov::ALL {
+            assert_eq!(aov.name().parse(), Ok(aov));
+        }
+        assert!("beauty".parse::<Aov>().is_err());
+    }
+}
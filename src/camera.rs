@@ -13,14 +13,9 @@ impl Camera {
     /// Create a ray coming from the center of a specified resolution pixel at viewport direction
     pub fn camera_ray(&self, px: Vec3) -> Ray {
         let vp_tr = ((px + Vec3::new(0.5, 0.5, 0.)) * self.viewport.size) / self.resolution;
-        let z = Vec3::new(0., 0., 1.).norm();
-        let y = z.cross(&self.viewport.dir).norm();
-        let top = self
-            .viewport
-            .dir
-            .cross(&y)
-            .mul_n(self.viewport.size.y / 2. - vp_tr.y);
-        let left = y.mul_n(self.viewport.size.x / 2. - vp_tr.x);
+        let (right, up, _) = self.axes();
+        let top = up.mul_n(self.viewport.size.y / 2. - vp_tr.y);
+        let left = right.mul_n(vp_tr.x - self.viewport.size.x / 2.);
         let vp_p = top + left;
         let fp = self.viewport.dir.mul_n(-self.focal_len);
         let dir = (vp_p - fp).norm();
@@ -30,6 +25,14 @@ impl Camera {
         }
     }
 
+    /// Unit vectors pointing right, up and forward from the camera point of view
+    pub fn axes(&self) -> (Vec3, Vec3, Vec3) {
+        let z = Vec3::new(0., 0., 1.).norm();
+        let left = z.cross(&self.viewport.dir).norm();
+        let up = self.viewport.dir.cross(&left);
+        (-left, up, self.viewport.dir)
+    }
+
     /// Camera field of view in radians
     pub fn fov(&self) -> f32 {
         let diag = self.viewport.size.x.hypot(self.viewport.size.y);
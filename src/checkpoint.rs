@@ -0,0 +1,301 @@
+use std::fs::{read, rename, write};
+use std::io::{Error, ErrorKind, Result};
+
+use crate::aov::Aov;
+use crate::color::Color;
+use crate::film::{AovBuffer, Film, PixelStats};
+use crate::scene::Scene;
+use crate::settings::RenderSettings;
+use crate::shape::components;
+
+const MAGIC: &[u8; 8] = b"SUNNYCKP";
+const VERSION: u32 = 4;
+
+/// Save the film of a render in progress
+/// Sampling streams are derived from the seed and per-pixel sample counts, so together with the
+/// film sample counts the settings hash fully describes the generator state
+pub fn save_checkpoint(
+    path: &str,
+    scene: &Scene,
+    settings: &RenderSettings,
+    film: &Film,
+) -> Result<()> {
+    let mut content: Vec<u8> = vec![];
+    content.extend(MAGIC);
+    content.extend(VERSION.to_le_bytes());
+    content.extend(scene_hash(scene).to_le_bytes());
+    content.extend(settings_hash(settings).to_le_bytes());
+    content.extend((film.width as u64).to_le_bytes());
+    content.extend((film.height as u64).to_le_bytes());
+    for p in &film.pixels {
+        for v in [p.sum.r, p.sum.g, p.sum.b, p.sum_sq] {
+            content.extend(v.to_le_bytes());
+        }
+        content.extend((p.count as u64).to_le_bytes());
+    }
+    content.extend((film.aovs.len() as u64).to_le_bytes());
+    for b in &film.aovs {
+        let id = Aov::ALL.iter().position(|a| *a == b.aov).unwrap();
+        content.push(id as u8);
+        for v in &b.values {
+            for c in [v.r, v.g, v.b] {
+                content.extend(c.to_le_bytes());
+            }
+        }
+    }
+    // write to a temporary file first, so the previous checkpoint survives being interrupted
+    let tmp = format!("{path}.tmp");
+    write(&tmp, content)?;
+    rename(tmp, path)
+}
+
+/// Load the film saved with [save_checkpoint] to continue the render
+/// Fails if the checkpoint was made for a different scene or sampling settings
+pub fn load_checkpoint(path: &str, scene: &Scene, settings: &RenderSettings) -> Result<Film> {
+    let content = read(path)?;
+    let mut r = Reader(&content);
+    if r.take(MAGIC.len())? != MAGIC {
+        return Err(invalid("not a checkpoint file"));
+    }
+    if r.u32()? != VERSION {
+        return Err(invalid("unsupported checkpoint version"));
+    }
+    if r.u64()? != scene_hash(scene) {
+        return Err(invalid("checkpoint was made for a different scene"));
+    }
+    if r.u64()? != settings_hash(settings) {
+        return Err(invalid(
+            "checkpoint was made with different render settings",
+        ));
+    }
+    let width = r.u64()? as usize;
+    let height = r.u64()? as usize;
+    if (width, height)
+        != (
+            scene.camera.resolution.x as usize,
+            scene.camera.resolution.y as usize,
+        )
+    {
+        return Err(invalid("checkpoint resolution does not match the camera"));
+    }
+    let mut film = Film::new(width, height);
+    for p in film.pixels.iter_mut() {
+        *p = PixelStats {
+            sum: Color::rgb(r.f32()?, r.f32()?, r.f32()?),
+            sum_sq: r.f32()?,
+            count: r.u64()? as usize,
+        };
+    }
+    for _ in 0..r.u64()? {
+        let aov = *Aov::ALL
+            .get(r.take(1)?[0] as usize)
+            .ok_or_else(|| invalid("unknown AOV"))?;
+        let mut values = Vec::with_capacity(film.pixels.len());
+        for _ in 0..film.pixels.len() {
+            values.push(Color::rgb(r.f32()?, r.f32()?, r.f32()?));
+        }
+        film.aovs.push(AovBuffer { aov, values });
+    }
+    Ok(film)
+}
+
+/// Fingerprint of the camera along with the shapes and material factors of objects
+/// Hashes raw values instead of the debug output, which is neither stable nor cheap for large
+/// meshes, material textures are left out
+fn scene_hash(scene: &Scene) -> u64 {
+    let c = &scene.camera;
+    let camera = [
+        c.resolution,
+        c.viewport.center,
+        c.viewport.size,
+        c.viewport.dir,
+    ];
+    let mut values: Vec<f32> = components(&camera).collect();
+    values.push(c.focal_len);
+    for o in &scene.objects {
+        o.shape.fingerprint(&mut values);
+        let m = &o.material;
+        values.extend([m.roughness, m.specularity, m.color.r, m.color.g, m.color.b]);
+        values.extend([
+            m.luminosity,
+            m.emission_unit as u8 as f32,
+            m.temperature.unwrap_or(0.),
+        ]);
+    }
+    let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+    fnv1a(&bytes)
+}
+
+/// Fingerprint of the settings affecting the samples
+/// Sample budgets, tiling and region are left out, so they can be changed when resuming
+fn settings_hash(settings: &RenderSettings) -> u64 {
+    let key = (
+        settings.seed,
+        settings.reflection_depth,
+        settings.roulette_depth,
+    );
+    fnv1a(format!("{key:?}").as_bytes())
+}
+
+/// [FNV-1a](https://en.wikipedia.org/wiki/Fowler%E2%80%93Noll%E2%80%93Vo_hash_function) hash,
+/// stable between program versions unlike the std hasher
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |h, b| {
+        (h ^ *b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.0.len() < n {
+            return Err(Error::from(ErrorKind::UnexpectedEof));
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn f32(&mut self) -> Result<f32> {
+        Ok(f32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env::temp_dir;
+    use std::f32::consts::PI;
+    use std::fs::remove_file;
+    use std::ops::ControlFlow;
+
+    use crate::aov::Aov;
+    use crate::camera::Camera;
+    use crate::checkpoint::{load_checkpoint, save_checkpoint};
+    use crate::color::Color;
+    use crate::film::Film;
+    use crate::material::Material;
+    use crate::object::Object;
+    use crate::scene::Scene;
+    use crate::settings::RenderSettings;
+    use crate::shape::cuboid::{Cuboid, OrientedCuboid};
+    use crate::shape::plane::Plane;
+    use crate::shape::sphere::Sphere;
+    use crate::shape::Shape;
+    use crate::vec3::Vec3;
+
+    fn scene(radius: f32) -> Scene {
+        with_shape(Box::new(Sphere {
+            center: Vec3::zero(),
+            radius,
+        }))
+    }
+
+    fn with_shape(shape: Box<dyn Shape>) -> Scene {
+        let c_pos = Vec3::new(-1., -1., 1.).norm().mul_n(5.);
+        Scene {
+            camera: Camera {
+                resolution: Vec3::new(6., 4., 0.),
+                viewport: Plane {
+                    center: c_pos,
+                    size: Vec3::new(1., 1., 0.),
+                    dir: (Vec3::zero() - c_pos).norm(),
+                },
+                focal_len: 1.5,
+            },
+            objects: vec![Object {
+                shape,
+                material: Material {
+                    roughness: 1.,
+                    specularity: 0.5,
+                    color: Color::GREEN,
+                    luminosity: 0.,
+                    ..Default::default()
+                },
+            }],
+        }
+    }
+
+    fn path(name: &str) -> String {
+        let path = temp_dir().join(format!("sunny-{}-{name}", std::process::id()));
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn resume() {
+        let s = scene(1.);
+        let settings = RenderSettings {
+            pass_count: 4,
+            ..Default::default()
+        };
+        let mut film = Film::new(6, 4).with_aovs(&[Aov::Albedo, Aov::ObjectId]);
+        s.render_progressive(&settings, &mut film, 2, &Default::default(), |_| {
+            ControlFlow::Break(())
+        });
+        let p = path("resume.ckp");
+        save_checkpoint(&p, &s, &settings, &film).unwrap();
+
+        let mut film = load_checkpoint(&p, &s, &settings).unwrap();
+        assert!(film.pixels.iter().all(|p| p.count == 2));
+        s.render_progressive(
+            &settings,
+            &mut film,
+            usize::MAX,
+            &Default::default(),
+            |_| ControlFlow::Continue(()),
+        );
+        let mut full = Film::new(6, 4).with_aovs(&[Aov::Albedo, Aov::ObjectId]);
+        s.render_progressive(
+            &settings,
+            &mut full,
+            usize::MAX,
+            &Default::default(),
+            |_| ControlFlow::Continue(()),
+        );
+        assert_eq!(film, full);
+        remove_file(p).unwrap();
+    }
+
+    #[test]
+    fn refuse_mismatch() {
+        let settings = RenderSettings::default();
+        let p = path("mismatch.ckp");
+        save_checkpoint(&p, &scene(1.), &settings, &Film::new(6, 4)).unwrap();
+        assert!(load_checkpoint(&p, &scene(1.), &settings).is_ok());
+        assert!(load_checkpoint(&p, &scene(2.), &settings).is_err());
+        let other = RenderSettings {
+            seed: 1,
+            ..settings
+        };
+        assert!(load_checkpoint(&p, &scene(1.), &other).is_err());
+        let more_samples = RenderSettings {
+            pass_count: 1000,
+            ..settings
+        };
+        assert!(load_checkpoint(&p, &scene(1.), &more_samples).is_ok());
+
+        // same center and area, turned around z
+        let cuboid = |angle: f32| {
+            with_shape(Box::new(OrientedCuboid {
+                cuboid: Cuboid::new(Vec3::zero(), Vec3::new(1., 2., 3.)),
+                rotation: Vec3::new(0., 0., angle),
+            }))
+        };
+        save_checkpoint(&p, &cuboid(0.), &settings, &Film::new(6, 4)).unwrap();
+        assert!(load_checkpoint(&p, &cuboid(0.), &settings).is_ok());
+        assert!(load_checkpoint(&p, &cuboid(PI / 2.), &settings).is_err());
+        remove_file(p).unwrap();
+    }
+}
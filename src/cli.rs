@@ -0,0 +1,233 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::aov::Aov;
+use crate::denoise::{Denoise, MAX_ITERATIONS};
+use crate::settings::{AdaptiveSampling, RenderSettings};
+
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Args {
+    pub settings: RenderSettings,
+    /// Path to the glTF scene rendered instead of the demo scene
+    pub scene: Option<String>,
+    /// Path to save the per-pixel sample count heat map to
+    pub heat_map: Option<String>,
+    /// Number of samples per pixel in a single pass of progressive rendering
+    pub progressive: Option<usize>,
+    /// Minimal time between progressive rendering snapshots
+    pub snapshot_interval: Duration,
+    /// Render time after which the render is cancelled
+    pub time_limit: Option<Duration>,
+    /// Save only the render region instead of the full size image
+    pub crop: bool,
+    /// Path to periodically save the render progress to
+    pub checkpoint: Option<String>,
+    /// Minimal time between checkpoint saves
+    pub checkpoint_interval: Duration,
+    /// Continue the render saved in the checkpoint
+    pub resume: bool,
+    /// Arbitrary output variables to save alongside the image
+    pub aovs: Vec<Aov>,
+    /// Denoise the image before saving
+    pub denoise: Option<Denoise>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Args {
+            settings: RenderSettings::default(),
+            scene: None,
+            heat_map: None,
+            progressive: None,
+            snapshot_interval: Duration::ZERO,
+            time_limit: None,
+            crop: false,
+            checkpoint: None,
+            checkpoint_interval: CHECKPOINT_INTERVAL,
+            resume: false,
+            aovs: vec![],
+            denoise: None,
+        }
+    }
+}
+
+/// Parse command line arguments, excluding the program name
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--scene" => parsed.scene = Some(value(&arg, args.next())?),
+            "--seed" => parsed.settings.seed = value(&arg, args.next())?,
+            "--samples" => parsed.settings.pass_count = value(&arg, args.next())?,
+            "--adaptive" => {
+                parsed.settings.adaptive = Some(AdaptiveSampling {
+                    threshold: value(&arg, args.next())?,
+                    ..parsed.settings.adaptive.unwrap_or_default()
+                })
+            }
+            "--min-samples" => adaptive(&mut parsed).min_samples = value(&arg, args.next())?,
+            "--max-samples" => adaptive(&mut parsed).max_samples = value(&arg, args.next())?,
+            "--progressive" => match value(&arg, args.next())? {
+                0 => return Err("`--progressive` requires at least 1 sample per pass".to_string()),
+                n => parsed.progressive = Some(n),
+            },
+            "--snapshot-interval" => parsed.snapshot_interval = duration(&arg, args.next())?,
+            "--time-limit" => parsed.time_limit = Some(duration(&arg, args.next())?),
+            "--tile-size" => parsed.settings.tile_size = value(&arg, args.next())?,
+            "--tile-order" => parsed.settings.tile_order = value(&arg, args.next())?,
+            "--region" => parsed.settings.region = Some(value(&arg, args.next())?),
+            "--crop" => parsed.crop = true,
+            "--checkpoint" => parsed.checkpoint = Some(value(&arg, args.next())?),
+            "--checkpoint-interval" => parsed.checkpoint_interval = duration(&arg, args.next())?,
+            "--resume" => parsed.resume = true,
+            "--aovs" => {
+                let names: String = value(&arg, args.next())?;
+                parsed.aovs = names.split(',').map(str::parse).collect::<Result<_, _>>()?;
+            }
+            "--denoise" => {
+                parsed.denoise.get_or_insert_with(Default::default);
+            }
+            "--denoise-iterations" => match value(&arg, args.next())? {
+                n if n > MAX_ITERATIONS => {
+                    return Err(format!(
+                        "`--denoise-iterations` allows at most {MAX_ITERATIONS} passes"
+                    ))
+                }
+                n => {
+                    parsed
+                        .denoise
+                        .get_or_insert_with(Default::default)
+                        .iterations = n
+                }
+            },
+            "--heat-map" => parsed.heat_map = Some(value(&arg, args.next())?),
+            _ => return Err(format!("unknown argument `{arg}`")),
+        }
+    }
+    if parsed.resume && parsed.checkpoint.is_none() {
+        return Err("`--resume` requires `--checkpoint`".to_string());
+    }
+    Ok(parsed)
+}
+
+/// Adaptive sampling settings, enabling adaptive sampling with default threshold if necessary
+fn adaptive(args: &mut Args) -> &mut AdaptiveSampling {
+    args.settings.adaptive.get_or_insert_with(Default::default)
+}
+
+/// Duration in seconds
+fn duration(arg: &str, value: Option<String>) -> Result<Duration, String> {
+    Duration::try_from_secs_f32(self::value(arg, value)?)
+        .map_err(|e| format!("invalid value for `{arg}`: {e}"))
+}
+
+fn value<T: FromStr>(arg: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("missing value for `{arg}`"))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid value `{value}` for `{arg}`"))
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::aov::Aov;
+    use crate::cli::parse_args;
+    use crate::tile::TileOrder;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parse_scene() {
+        let a = parse_args(args("--scene a.glb")).unwrap();
+        assert_eq!(a.scene.as_deref(), Some("a.glb"));
+        assert!(parse_args(args("--scene")).is_err());
+    }
+
+    #[test]
+    fn parse_seed() {
+        let a = parse_args(args("--seed 42")).unwrap();
+        assert_eq!(a.settings.seed, 42);
+    }
+
+    #[test]
+    fn parse_adaptive() {
+        let a = parse_args(args("--min-samples 8 --adaptive 0.1 --heat-map h.ppm")).unwrap();
+        let adaptive = a.settings.adaptive.unwrap();
+        assert_eq!(adaptive.min_samples, 8);
+        assert_eq!(adaptive.threshold, 0.1);
+        assert_eq!(a.heat_map.as_deref(), Some("h.ppm"));
+    }
+
+    #[test]
+    fn parse_progressive() {
+        let a = parse_args(args("--progressive 4 --snapshot-interval 2.5")).unwrap();
+        assert_eq!(a.progressive, Some(4));
+        assert!(parse_args(args("--progressive 0")).is_err());
+        assert_eq!(a.snapshot_interval, Duration::from_millis(2500));
+        let a = parse_args(args("--time-limit 60")).unwrap();
+        assert_eq!(a.time_limit, Some(Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn parse_tiles() {
+        let a = parse_args(args("--tile-size 16 --tile-order hilbert")).unwrap();
+        assert_eq!(a.settings.tile_size, 16);
+        assert_eq!(a.settings.tile_order, TileOrder::Hilbert);
+    }
+
+    #[test]
+    fn parse_region() {
+        let a = parse_args(args("--region 10,20,30,40 --crop")).unwrap();
+        let region = a.settings.region.unwrap();
+        assert_eq!(
+            (region.x, region.y, region.width, region.height),
+            (10, 20, 30, 40)
+        );
+        assert!(a.crop);
+    }
+
+    #[test]
+    fn parse_checkpoint() {
+        let a = parse_args(args("--checkpoint r.ckp --resume --checkpoint-interval 10")).unwrap();
+        assert_eq!(a.checkpoint.as_deref(), Some("r.ckp"));
+        assert!(a.resume);
+        assert_eq!(a.checkpoint_interval, Duration::from_secs(10));
+    }
+
+    #[test]
+    fn parse_aovs() {
+        let a = parse_args(args("--aovs depth,object_id")).unwrap();
+        assert_eq!(a.aovs, vec![Aov::Depth, Aov::ObjectId]);
+    }
+
+    #[test]
+    fn parse_denoise() {
+        assert_eq!(
+            parse_args(args("--denoise")).unwrap().denoise,
+            Some(Default::default())
+        );
+        let a = parse_args(args("--denoise-iterations 2")).unwrap();
+        assert_eq!(a.denoise.unwrap().iterations, 2);
+        assert!(parse_args(args("--denoise-iterations 16")).is_ok());
+        assert!(parse_args(args("--denoise-iterations 64")).is_err());
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(parse_args(args("--seed")).is_err());
+        assert!(parse_args(args("--seed x")).is_err());
+        assert!(parse_args(args("--snapshot-interval -1")).is_err());
+        assert!(parse_args(args("--tile-order zigzag")).is_err());
+        assert!(parse_args(args("--region 1,2")).is_err());
+        assert!(parse_args(args("--resume")).is_err());
+        assert!(parse_args(args("--aovs depth,beauty")).is_err());
+        assert!(parse_args(args("--foo")).is_err());
+    }
+}
@@ -1,5 +1,5 @@
 use std::cmp::{max_by, min_by};
-use std::ops::{Add, Div};
+use std::ops::{Add, Div, Mul};
 
 #[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Default)]
 pub struct Color {
@@ -37,6 +37,15 @@ impl Color {
         }
     }
 
+    /// Linear interpolation between colors, `t` of 0 gives `self` and 1 gives `other`
+    pub fn lerp(&self, other: &Color, t: f32) -> Color {
+        Color::rgb(
+            self.r + (other.r - self.r) * t,
+            self.g + (other.g - self.g) * t,
+            self.b + (other.b - self.b) * t,
+        )
+    }
+
     pub fn clamp(&self) -> Color {
         Color {
             r: (self.r).clamp(0., 1.),
@@ -113,5 +122,16 @@ impl Add for Color {
     }
 }
 
+impl Mul for Color {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self::Output {
+        Color {
+            r: self.r * rhs.r,
+            g: self.g * rhs.g,
+            b: self.b * rhs.b,
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {}
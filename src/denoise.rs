@@ -0,0 +1,149 @@
+use rayon::prelude::*;
+
+use crate::color::Color;
+use crate::image::Image;
+
+/// Settings of the edge-avoiding À-Trous wavelet filter
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Denoise {
+    /// Number of filter passes, each doubling the filter footprint
+    pub iterations: usize,
+    /// Sensitivity to pixel color differences, halved every pass
+    pub sigma_color: f32,
+    pub sigma_normal: f32,
+    pub sigma_albedo: f32,
+}
+
+impl Default for Denoise {
+    fn default() -> Self {
+        Denoise {
+            iterations: 5,
+            sigma_color: 0.5,
+            sigma_normal: 0.3,
+            sigma_albedo: 0.1,
+        }
+    }
+}
+
+/// Largest number of filter passes, later passes would step over images of any practical size
+pub const MAX_ITERATIONS: usize = 16;
+
+/// B3 spline kernel weights
+const KERNEL: [f32; 5] = [1. / 16., 1. / 4., 3. / 8., 1. / 4., 1. / 16.];
+
+/// Denoise the image using albedo and normal feature buffers of the same size to keep edges
+/// [ref](https://jo.dreggn.org/home/2010_atrous.pdf)
+pub fn denoise(image: &Image, albedo: &Image, normal: &Image, settings: &Denoise) -> Image {
+    assert_eq!((image.width, image.height), (albedo.width, albedo.height));
+    assert_eq!((image.width, image.height), (normal.width, normal.height));
+    let mut current = image.clone();
+    for i in 0..settings.iterations.min(MAX_ITERATIONS) {
+        let step = 1 << i;
+        let sigma_color = settings.sigma_color / step as f32;
+        let pixels = (0..current.pixels.len())
+            .into_par_iter()
+            .map(|p| {
+                let (x, y) = (p % image.width, p / image.width);
+                let c = current.pixels[p];
+                let mut sum = Color::BLACK;
+                let mut weights = 0.;
+                for (ky, hy) in KERNEL.iter().enumerate() {
+                    for (kx, hx) in KERNEL.iter().enumerate() {
+                        let qx = x as isize + (kx as isize - 2) * step;
+                        let qy = y as isize + (ky as isize - 2) * step;
+                        if qx < 0
+                            || qy < 0
+                            || qx >= image.width as isize
+                            || qy >= image.height as isize
+                        {
+                            continue;
+                        }
+                        let q = qy as usize * image.width + qx as usize;
+                        let w = hx
+                            * hy
+                            * weight(c, current.pixels[q], sigma_color)
+                            * weight(normal.pixels[p], normal.pixels[q], settings.sigma_normal)
+                            * weight(albedo.pixels[p], albedo.pixels[q], settings.sigma_albedo);
+                        let cq = current.pixels[q];
+                        sum = Color::rgb(sum.r + cq.r * w, sum.g + cq.g * w, sum.b + cq.b * w);
+                        weights += w;
+                    }
+                }
+                // center pixel weight is never zero
+                Color::rgb(sum.r / weights, sum.g / weights, sum.b / weights)
+            })
+            .collect();
+        current = Image {
+            width: image.width,
+            height: image.height,
+            pixels,
+        };
+    }
+    current
+}
+
+/// Edge-stopping weight, decreasing with feature difference
+fn weight(a: Color, b: Color, sigma: f32) -> f32 {
+    let d = Color::rgb(a.r - b.r, a.g - b.g, a.b - b.b);
+    let dist_sq = d.r * d.r + d.g * d.g + d.b * d.b;
+    (-dist_sq / (sigma * sigma).max(f32::EPSILON)).exp()
+}
+
+#[cfg(test)]
+mod test {
+    use rand::Rng;
+
+    use crate::color::Color;
+    use crate::denoise::{denoise, Denoise};
+    use crate::image::Image;
+    use crate::sampler::sample_rng;
+
+    fn noisy(w: usize, h: usize, base: impl Fn(usize, usize) -> f32) -> Image {
+        let mut rng = sample_rng(0, 0, 0);
+        let mut image = Image::new(w, h);
+        for y in 0..h {
+            for x in 0..w {
+                let v = base(x, y) + rng.gen_range(-0.1..0.1);
+                image.set(x, y, Color::mono(v));
+            }
+        }
+        image
+    }
+
+    fn variance(image: &Image, base: impl Fn(usize, usize) -> f32) -> f32 {
+        let mut sum = 0.;
+        for y in 0..image.height {
+            for x in 0..image.width {
+                sum += (image.get(x, y).r - base(x, y)).powi(2);
+            }
+        }
+        sum / image.pixels.len() as f32
+    }
+
+    #[test]
+    fn reduces_noise() {
+        let base = |_, _| 0.5;
+        let image = noisy(32, 32, base);
+        let flat = Image {
+            pixels: vec![Color::WHITE; 32 * 32],
+            ..image.clone()
+        };
+        let denoised = denoise(&image, &flat, &flat, &Denoise::default());
+        assert!(variance(&denoised, base) < variance(&image, base) / 10.);
+    }
+
+    #[test]
+    fn keeps_albedo_edges() {
+        let base = |x, _| if x < 16 { 0.2 } else { 0.8 };
+        let image = noisy(32, 32, base);
+        let mut albedo = Image::new(32, 32);
+        let normal = albedo.clone();
+        for y in 0..32 {
+            for x in 0..32 {
+                albedo.set(x, y, Color::mono(base(x, y)));
+            }
+        }
+        let denoised = denoise(&image, &albedo, &normal, &Denoise::default());
+        assert!(variance(&denoised, base) < variance(&image, base) / 5.);
+    }
+}
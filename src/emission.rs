@@ -0,0 +1,111 @@
+use crate::color::Color;
+use std::f32::consts::PI;
+
+/// Luminance in nits of the white emission with radiance 1
+pub const REFERENCE_LUMINANCE: f32 = 100.;
+
+/// Lumens per watt, for the 555nm light the eye is most sensitive to
+pub const LUMINOUS_EFFICACY: f32 = 683.;
+
+/// Unit of the material emission strength
+/// Physical units describe white emission, colored emission is scaled by its color
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum EmissionUnit {
+    /// Radiance multiplier
+    #[default]
+    Relative,
+    /// Radiant flux of the whole emitter, converted with `LUMINOUS_EFFICACY`
+    Watts,
+    /// Luminous flux of the whole emitter
+    Lumens,
+    /// Luminance, candela per square meter
+    Nits,
+}
+
+impl EmissionUnit {
+    /// Radiance of a diffuse emitter with the `area` and the emission `strength` in this unit
+    pub fn radiance(&self, strength: f32, area: f32) -> f32 {
+        // flux of a diffuse emitter is luminance integrated over the hemisphere and the area
+        let flux_luminance = |lumens: f32| lumens / (PI * area);
+        match self {
+            EmissionUnit::Relative => strength,
+            EmissionUnit::Watts => {
+                flux_luminance(strength * LUMINOUS_EFFICACY) / REFERENCE_LUMINANCE
+            }
+            EmissionUnit::Lumens => flux_luminance(strength) / REFERENCE_LUMINANCE,
+            EmissionUnit::Nits => strength / REFERENCE_LUMINANCE,
+        }
+    }
+}
+
+/// Linear sRGB color of the blackbody radiator at the `temperature` in Kelvin, with luminance
+/// of 1
+/// Planck's law spectrum is integrated with CIE 1931 color matching functions and converted
+/// from XYZ, colors outside of the sRGB gamut are clipped
+pub fn blackbody(temperature: f32) -> Color {
+    let t = temperature.max(1.) as f64;
+    let (mut x, mut y, mut z) = (0., 0., 0.);
+    for nm in (380..=780).step_by(5) {
+        let l = nm as f64 * 1e-9;
+        // constant factors are dropped, the result is normalized
+        let power = 1. / (l.powi(5) * ((1.438_776_9e-2 / (l * t)).exp() - 1.));
+        let (cx, cy, cz) = cie_xyz(nm as f32);
+        x += power * cx as f64;
+        y += power * cy as f64;
+        z += power * cz as f64;
+    }
+    let (x, y, z) = ((x / y) as f32, 1., (z / y) as f32);
+    Color::rgb(
+        (3.2406 * x - 1.5372 * y - 0.4986 * z).max(0.),
+        (-0.9689 * x + 1.8758 * y + 0.0415 * z).max(0.),
+        (0.0557 * x - 0.2040 * y + 1.0570 * z).max(0.),
+    )
+}
+
+/// CIE 1931 2° color matching functions at the `wavelength` in nanometers
+/// [Multi-lobe fit](https://jcgt.org/published/0002/02/01/)
+fn cie_xyz(wavelength: f32) -> (f32, f32, f32) {
+    let g = |mu: f32, s1: f32, s2: f32| {
+        let s = if wavelength < mu { s1 } else { s2 };
+        (-0.5 * ((wavelength - mu) / s).powi(2)).exp()
+    };
+    (
+        1.056 * g(599.8, 37.9, 31.0) + 0.362 * g(442.0, 16.0, 26.7) - 0.065 * g(501.1, 20.4, 26.2),
+        0.821 * g(568.8, 46.9, 40.5) + 0.286 * g(530.9, 16.3, 31.1),
+        1.217 * g(437.0, 11.8, 36.0) + 0.681 * g(459.0, 26.0, 13.8),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::f32::consts::PI;
+
+    use crate::emission::{blackbody, EmissionUnit};
+    use crate::math::approx_eq;
+
+    #[test]
+    fn blackbody_colors() {
+        let candle = blackbody(1900.);
+        assert!(candle.r > candle.g && candle.g > candle.b, "{candle:?}");
+        let sky = blackbody(12000.);
+        assert!(sky.b > sky.g && sky.g > sky.r, "{sky:?}");
+        // D65 white point is close to the 6504K blackbody
+        let white = blackbody(6504.);
+        for c in [white.r, white.g, white.b] {
+            assert!((c - 1.).abs() < 0.1, "{white:?}");
+        }
+        let luminance = 0.2126 * white.r + 0.7152 * white.g + 0.0722 * white.b;
+        assert!(approx_eq(luminance, 1.));
+    }
+
+    #[test]
+    fn units() {
+        assert_eq!(EmissionUnit::Relative.radiance(5., 2.), 5.);
+        assert_eq!(EmissionUnit::Nits.radiance(250., 2.), 2.5);
+        assert!(approx_eq(EmissionUnit::Lumens.radiance(100. * PI, 1.), 1.));
+        assert!(approx_eq(
+            EmissionUnit::Watts.radiance(PI, 683.),
+            EmissionUnit::Lumens.radiance(PI, 1.)
+        ));
+    }
+}
@@ -0,0 +1,263 @@
+use crate::aov::Aov;
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::image::Image;
+use crate::scene::Sample;
+use crate::tile::Tile;
+
+/// Running statistics of all samples taken for a single pixel
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Default)]
+pub struct PixelStats {
+    pub sum: Color,
+    /// Sum of squared sample lightness, used for variance estimation
+    pub sum_sq: f32,
+    pub count: usize,
+}
+
+impl PixelStats {
+    pub fn add(&mut self, c: Color) {
+        self.sum = Color::rgb(self.sum.r + c.r, self.sum.g + c.g, self.sum.b + c.b);
+        self.sum_sq += c.lightness() * c.lightness();
+        self.count += 1;
+    }
+
+    pub fn mean(&self) -> Color {
+        if self.count == 0 {
+            return Color::BLACK;
+        }
+        let n = self.count as f32;
+        Color::rgb(self.sum.r / n, self.sum.g / n, self.sum.b / n)
+    }
+
+    /// Unbiased sample variance of the pixel lightness
+    pub fn variance(&self) -> f32 {
+        if self.count < 2 {
+            return 0.;
+        }
+        let n = self.count as f32;
+        let mean = self.sum.lightness() / n;
+        ((self.sum_sq - n * mean * mean) / (n - 1.)).max(0.)
+    }
+
+    /// Standard error of the pixel lightness estimate
+    pub fn error(&self) -> f32 {
+        if self.count == 0 {
+            return f32::INFINITY;
+        }
+        (self.variance() / self.count as f32).sqrt()
+    }
+}
+
+/// Per-pixel values of an arbitrary output variable
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct AovBuffer {
+    pub aov: Aov,
+    /// Sum of sample values for averaged AOVs, the first sample value otherwise
+    pub values: Vec<Color>,
+}
+
+/// Accumulation buffer collecting samples of every image pixel
+#[derive(Debug, Clone, PartialEq, PartialOrd, Default)]
+pub struct Film {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<PixelStats>,
+    pub aovs: Vec<AovBuffer>,
+}
+
+impl Film {
+    pub fn new(width: usize, height: usize) -> Film {
+        Film {
+            width,
+            height,
+            pixels: vec![PixelStats::default(); width * height],
+            aovs: vec![],
+        }
+    }
+
+    /// Also collect the AOVs from every sample
+    pub fn with_aovs(mut self, aovs: &[Aov]) -> Film {
+        for aov in aovs {
+            if self.aov(*aov).is_none() {
+                self.aovs.push(AovBuffer {
+                    aov: *aov,
+                    values: vec![Color::BLACK; self.pixels.len()],
+                });
+            }
+        }
+        self
+    }
+
+    pub fn aov(&self, aov: Aov) -> Option<&AovBuffer> {
+        self.aovs.iter().find(|b| b.aov == aov)
+    }
+
+    /// Add a sample of the pixel with index `i`
+    pub fn add_sample(&mut self, i: usize, sample: &Sample, camera: &Camera) {
+        let first = self.pixels[i].count == 0;
+        self.pixels[i].add(sample.color);
+        for b in self.aovs.iter_mut() {
+            let v = b.aov.value(sample, camera);
+            let acc = &mut b.values[i];
+            if b.aov.averaged() {
+                *acc = Color::rgb(acc.r + v.r, acc.g + v.g, acc.b + v.b);
+            } else if first {
+                *acc = v;
+            }
+        }
+    }
+
+    /// Tile covering the whole film
+    pub fn area(&self) -> Tile {
+        Tile {
+            x: 0,
+            y: 0,
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// Film containing only the pixels within the tile
+    pub fn crop(&self, tile: &Tile) -> Film {
+        Film {
+            width: tile.width,
+            height: tile.height,
+            pixels: crop_rows(&self.pixels, self.width, tile),
+            aovs: self
+                .aovs
+                .iter()
+                .map(|b| AovBuffer {
+                    aov: b.aov,
+                    values: crop_rows(&b.values, self.width, tile),
+                })
+                .collect(),
+        }
+    }
+
+    /// Replace pixels within the tile with the pixels of the tile film
+    pub fn set_tile(&mut self, tile: &Tile, film: &Film) {
+        set_rows(&mut self.pixels, self.width, tile, &film.pixels);
+        for b in self.aovs.iter_mut() {
+            if let Some(src) = film.aov(b.aov) {
+                set_rows(&mut b.values, self.width, tile, &src.values);
+            }
+        }
+    }
+
+    pub fn image(&self) -> Image {
+        Image {
+            width: self.width,
+            height: self.height,
+            pixels: self.pixels.iter().map(|p| p.mean()).collect(),
+        }
+    }
+
+    /// Per-pixel AOV values, if the AOV is collected
+    pub fn aov_image(&self, aov: Aov) -> Option<Image> {
+        let b = self.aov(aov)?;
+        Some(Image {
+            width: self.width,
+            height: self.height,
+            pixels: b
+                .values
+                .iter()
+                .zip(&self.pixels)
+                .map(|(v, p)| {
+                    if !aov.averaged() || p.count == 0 {
+                        return *v;
+                    }
+                    let n = p.count as f32;
+                    Color::rgb(v.r / n, v.g / n, v.b / n)
+                })
+                .collect(),
+        })
+    }
+
+    /// Visualize the number of samples spent on every pixel
+    /// Black pixels received no samples, white pixels received the most samples
+    pub fn heat_map(&self) -> Image {
+        let max = self
+            .pixels
+            .iter()
+            .map(|p| p.count)
+            .max()
+            .unwrap_or(0)
+            .max(1) as f32;
+        Image {
+            width: self.width,
+            height: self.height,
+            pixels: self
+                .pixels
+                .iter()
+                .map(|p| {
+                    let t = 3. * p.count as f32 / max;
+                    Color::rgb(t, t - 1., t - 2.).clamp()
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Tile part of the row by row buffer of an image of the specified width
+fn crop_rows<T: Copy>(buf: &[T], width: usize, tile: &Tile) -> Vec<T> {
+    (tile.y..tile.y + tile.height)
+        .flat_map(|y| {
+            let row = y * width + tile.x;
+            buf[row..row + tile.width].iter().copied()
+        })
+        .collect()
+}
+
+fn set_rows<T: Copy>(buf: &mut [T], width: usize, tile: &Tile, src: &[T]) {
+    for (ty, row) in src.chunks(tile.width).enumerate() {
+        let start = (tile.y + ty) * width + tile.x;
+        buf[start..start + tile.width].copy_from_slice(row);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::color::Color;
+    use crate::film::{Film, PixelStats};
+    use crate::math::approx_eq;
+    use crate::tile::Tile;
+
+    #[test]
+    fn mean_variance() {
+        let mut p = PixelStats::default();
+        for l in [0.2, 0.4, 0.6, 0.8] {
+            p.add(Color::mono(l));
+        }
+        assert!(approx_eq(p.mean().r, 0.5));
+        assert!(approx_eq(p.variance(), 0.2 / 3.));
+    }
+
+    #[test]
+    fn tile_roundtrip() {
+        let mut film = Film::new(5, 4);
+        let tile = Tile {
+            x: 1,
+            y: 2,
+            width: 3,
+            height: 2,
+        };
+        let mut cropped = film.crop(&tile);
+        assert_eq!(cropped.pixels.len(), 6);
+        cropped.pixels[4].add(Color::WHITE);
+        film.set_tile(&tile, &cropped);
+        let counts: Vec<_> = film.pixels.iter().map(|p| p.count).collect();
+        assert_eq!(counts.iter().sum::<usize>(), 1);
+        assert_eq!(counts[3 * 5 + 2], 1);
+    }
+
+    #[test]
+    fn constant_no_error() {
+        let mut p = PixelStats::default();
+        assert_eq!(p.error(), f32::INFINITY);
+        for _ in 0..4 {
+            p.add(Color::RED);
+        }
+        assert_eq!(p.mean(), Color::RED);
+        assert!(approx_eq(p.error(), 0.));
+    }
+}
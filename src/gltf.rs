@@ -0,0 +1,1016 @@
+use std::f32::consts::PI;
+use std::fs::read;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::emission::EmissionUnit;
+use crate::image::Image;
+use crate::json::Json;
+use crate::material::Material;
+use crate::object::Object;
+use crate::scene::Scene;
+use crate::shape::disk::Disk;
+use crate::shape::mesh::Mesh;
+use crate::shape::plane::Plane;
+use crate::shape::sphere::Sphere;
+use crate::shape::Shape;
+use crate::texture::channel::Channel;
+use crate::texture::image::{Filter, ImageTexture, Wrap};
+use crate::texture::multiply::Multiply;
+use crate::texture::vertex_color::VertexColor;
+use crate::texture::Texture;
+use crate::transform::{Matrix4, Transform};
+use crate::vec3::Vec3;
+
+/// Radius of spheres standing in for point and spot lights
+const LIGHT_RADIUS: f32 = 0.05;
+/// Distance of disks standing in for directional lights
+const SUN_DISTANCE: f32 = 1e4;
+/// Angular radius of disks standing in for directional lights, in radians
+const SUN_ANGULAR_RADIUS: f32 = 0.05;
+/// Specularity of non-metallic materials, metals reflect their own color with specularity 0
+const DIELECTRIC_SPECULARITY: f32 = 0.04;
+/// Vertical field of view of the camera framing scenes without cameras
+const DEFAULT_FOV: f32 = PI / 4.;
+
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const CHUNK_JSON: u32 = 0x4E4F_534A;
+const CHUNK_BIN: u32 = 0x004E_4942;
+
+/// Load the [glTF 2.0](https://registry.khronos.org/glTF/specs/2.0/glTF-2.0.html) scene from
+/// the `.gltf` or `.glb` file, with the camera rendering at the `resolution`
+///
+/// The default scene is imported with its node hierarchy, turned from the y up to the z up
+/// space. Every triangle primitive becomes an object instancing the mesh shared between nodes.
+/// Metallic-roughness materials are mapped onto the renderer material, metals reflect their
+/// base color and non-metals reflect a bit of white light. PNG textures are imported, other
+/// images are skipped, leaving the material factors alone, and returned next to the scene.
+///
+/// The first perspective camera views the scene, its roll is dropped as cameras keep z up.
+/// Scenes without a camera are framed from the front. `KHR_lights_punctual` point and spot lights
+/// become small emissive spheres, ignoring spot cones and light range, and directional lights
+/// become distant emissive disks.
+pub fn load_gltf(path: &str, resolution: Vec3) -> std::io::Result<(Scene, Vec<usize>)> {
+    let dir = Path::new(path).parent().unwrap_or(Path::new(""));
+    parse_gltf(&read(path)?, dir, resolution)
+}
+
+/// Parse the glTF or binary glTF file, external files are looked up relative to `dir`
+/// Indices of images skipped for their unsupported format are returned along with the scene
+pub fn parse_gltf(
+    data: &[u8],
+    dir: &Path,
+    resolution: Vec3,
+) -> std::io::Result<(Scene, Vec<usize>)> {
+    let (json, bin) = if data.starts_with(GLB_MAGIC) {
+        parse_glb(data)?
+    } else {
+        (data, None)
+    };
+    let json = std::str::from_utf8(json).map_err(|_| invalid("invalid glTF JSON"))?;
+    let mut gltf = Gltf::new(Json::parse(json)?, bin, dir)?;
+    let scene = gltf.scene(resolution)?;
+    Ok((scene, gltf.skipped_images))
+}
+
+/// JSON and binary chunks of the GLB container
+fn parse_glb(data: &[u8]) -> std::io::Result<(&[u8], Option<&[u8]>)> {
+    let int = |pos: usize| {
+        data.get(pos..pos + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or_else(|| invalid("unexpected end of GLB file"))
+    };
+    if int(4)? != 2 {
+        return Err(invalid("unsupported GLB version"));
+    }
+    let (mut json, mut bin) = (None, None);
+    let mut pos = 12;
+    while pos + 8 <= data.len() {
+        let (length, kind) = (int(pos)? as usize, int(pos + 4)?);
+        let chunk = data
+            .get(pos + 8..pos + 8 + length)
+            .ok_or_else(|| invalid("unexpected end of GLB file"))?;
+        match kind {
+            CHUNK_JSON if json.is_none() => json = Some(chunk),
+            CHUNK_BIN if bin.is_none() => bin = Some(chunk),
+            _ => {}
+        }
+        pos += 8 + length;
+    }
+    Ok((
+        json.ok_or_else(|| invalid("no JSON chunk in GLB file"))?,
+        bin,
+    ))
+}
+
+/// Shape shared by the instances of the mesh primitive, along with its material and bounds
+type Primitive = (Arc<dyn Shape>, Material, [Vec3; 2]);
+
+struct Gltf {
+    json: Json,
+    buffers: Vec<Vec<u8>>,
+    /// Textures by glTF texture index, `None` for unsupported images
+    textures: Vec<Option<Arc<dyn Texture>>>,
+    /// Indices of images in unsupported formats
+    skipped_images: Vec<usize>,
+    /// Primitives by glTF mesh index, loaded by the first node using the mesh
+    meshes: Vec<Option<Vec<Primitive>>>,
+    /// Nodes already placed, every node has at most one parent
+    visited: Vec<bool>,
+    objects: Vec<Object>,
+    camera: Option<Camera>,
+    /// Bounds of placed meshes, framed by the default camera
+    bounds: Option<[Vec3; 2]>,
+}
+
+impl Gltf {
+    fn new(json: Json, bin: Option<&[u8]>, dir: &Path) -> std::io::Result<Gltf> {
+        let mut buffers = vec![];
+        for (i, buffer) in array(&json, "buffers").iter().enumerate() {
+            buffers.push(match buffer.get("uri").and_then(Json::as_str) {
+                Some(uri) => load_uri(uri, dir)?,
+                None if i == 0 => bin
+                    .ok_or_else(|| invalid("glTF buffer without data"))?
+                    .to_vec(),
+                None => return Err(invalid("glTF buffer without data")),
+            });
+        }
+        let mut gltf = Gltf {
+            meshes: vec![None; array(&json, "meshes").len()],
+            visited: vec![false; array(&json, "nodes").len()],
+            json,
+            buffers,
+            textures: vec![],
+            skipped_images: vec![],
+            objects: vec![],
+            camera: None,
+            bounds: None,
+        };
+        for i in 0..array(&gltf.json, "textures").len() {
+            let texture = gltf.texture(i, dir)?;
+            gltf.textures.push(texture);
+        }
+        Ok(gltf)
+    }
+
+    fn scene(&mut self, resolution: Vec3) -> std::io::Result<Scene> {
+        let index = self.json.get("scene").and_then(Json::as_usize).unwrap_or(0);
+        let roots: Vec<usize> = match array(&self.json, "scenes").get(index) {
+            Some(scene) => indices(scene, "nodes"),
+            // without scenes, every node which is not a child is a root
+            None => {
+                let nodes = array(&self.json, "nodes");
+                let children: Vec<usize> =
+                    nodes.iter().flat_map(|n| indices(n, "children")).collect();
+                (0..nodes.len()).filter(|i| !children.contains(i)).collect()
+            }
+        };
+        // glTF y axis points up, turning it to the z axis
+        let up = Transform::rotate_x(PI / 2.);
+        for node in roots {
+            self.node(node, &up, resolution)?;
+        }
+        let camera = self
+            .camera
+            .unwrap_or_else(|| default_camera(self.bounds, resolution));
+        Ok(Scene {
+            camera,
+            objects: std::mem::take(&mut self.objects),
+        })
+    }
+
+    fn node(&mut self, index: usize, parent: &Transform, resolution: Vec3) -> std::io::Result<()> {
+        let node = item(&self.json, "nodes", index)?.clone();
+        // nodes form disjoint trees, a node reached twice is shared or part of a cycle
+        if std::mem::replace(&mut self.visited[index], true) {
+            return Err(invalid("glTF node with several parents"));
+        }
+        // nodes scaled to zero are hidden along with their children
+        let Some(local) = node_transform(&node) else {
+            return Ok(());
+        };
+        let world = local.then(parent);
+        if let Some(mesh) = node.get("mesh").and_then(Json::as_usize) {
+            self.mesh(mesh, &world)?;
+        }
+        if let Some(camera) = node.get("camera").and_then(Json::as_usize) {
+            if self.camera.is_none() {
+                self.camera = self.perspective(camera, &world, resolution)?;
+            }
+        }
+        let light = node
+            .get("extensions")
+            .and_then(|e| e.get("KHR_lights_punctual"))
+            .and_then(|l| l.get("light"))
+            .and_then(Json::as_usize);
+        if let Some(light) = light {
+            let light = self.light(light, &world)?;
+            self.objects.push(light);
+        }
+        for child in indices(&node, "children") {
+            self.node(child, &world, resolution)?;
+        }
+        Ok(())
+    }
+
+    fn mesh(&mut self, index: usize, world: &Transform) -> std::io::Result<()> {
+        if self.meshes.get(index).is_none() {
+            return Err(invalid(&format!("missing glTF mesh {index}")));
+        }
+        if self.meshes[index].is_none() {
+            let mut primitives = vec![];
+            for p in array(item(&self.json, "meshes", index)?, "primitives") {
+                primitives.extend(self.primitive(p)?);
+            }
+            self.meshes[index] = Some(primitives);
+        }
+        for (shape, material, [min, max]) in self.meshes[index].clone().unwrap_or_default() {
+            self.objects.push(Object::instance(shape, *world, material));
+            for i in 0..8 {
+                let corner = Vec3::new(
+                    if i & 1 == 0 { min.x } else { max.x },
+                    if i & 2 == 0 { min.y } else { max.y },
+                    if i & 4 == 0 { min.z } else { max.z },
+                );
+                let p = world.point(&corner);
+                let [lo, hi] = self.bounds.get_or_insert([p, p]);
+                *lo = Vec3::new(lo.x.min(p.x), lo.y.min(p.y), lo.z.min(p.z));
+                *hi = Vec3::new(hi.x.max(p.x), hi.y.max(p.y), hi.z.max(p.z));
+            }
+        }
+        Ok(())
+    }
+
+    /// Triangle mesh of the primitive, other primitive modes and primitives without vertices
+    /// are skipped
+    fn primitive(&self, p: &Json) -> std::io::Result<Option<Primitive>> {
+        if p.get("mode").and_then(Json::as_usize).unwrap_or(4) != 4 {
+            return Ok(None);
+        }
+        let attribute = |name: &str| {
+            p.get("attributes")
+                .and_then(|a| a.get(name))
+                .and_then(Json::as_usize)
+        };
+        let Some(position) = attribute("POSITION") else {
+            return Ok(None);
+        };
+        let positions = vectors(self.accessor(position)?)?;
+        if positions.is_empty() {
+            return Ok(None);
+        }
+        let triangles: Vec<[usize; 3]> = match p.get("indices").and_then(Json::as_usize) {
+            Some(i) => self
+                .accessor(i)?
+                .1
+                .chunks_exact(3)
+                .map(|t| [t[0] as usize, t[1] as usize, t[2] as usize])
+                .collect(),
+            None => (0..positions.len() / 3)
+                .map(|i| [3 * i, 3 * i + 1, 3 * i + 2])
+                .collect(),
+        };
+        if triangles.iter().flatten().any(|i| *i >= positions.len()) {
+            return Err(invalid("glTF primitive refers to missing vertex"));
+        }
+        let n = positions.len();
+        let bounds = positions.iter().fold([positions[0]; 2], |[lo, hi], p| {
+            [
+                Vec3::new(lo.x.min(p.x), lo.y.min(p.y), lo.z.min(p.z)),
+                Vec3::new(hi.x.max(p.x), hi.y.max(p.y), hi.z.max(p.z)),
+            ]
+        });
+        let mut mesh = Mesh::new(positions, triangles);
+        if let Some(i) = attribute("NORMAL") {
+            let normals = vectors(self.accessor(i)?)?;
+            if normals.len() == n {
+                mesh = mesh.with_normals(normals.iter().map(|n| n.norm()).collect());
+            }
+        }
+        if let Some(i) = attribute("TEXCOORD_0") {
+            let (components, values) = self.accessor(i)?;
+            if components == 2 && values.len() == 2 * n {
+                mesh = mesh.with_uvs(
+                    values
+                        .chunks_exact(2)
+                        .map(|uv| (uv[0] as f32, uv[1] as f32))
+                        .collect(),
+                );
+            }
+        }
+        if let Some(i) = attribute("COLOR_0") {
+            let (components, values) = self.accessor(i)?;
+            if components >= 3 && values.len() == components * n {
+                mesh = mesh.with_colors(
+                    values
+                        .chunks_exact(components)
+                        .map(|c| Color::rgb(c[0] as f32, c[1] as f32, c[2] as f32))
+                        .collect(),
+                );
+            }
+        }
+        let material = self.material(
+            p.get("material").and_then(Json::as_usize),
+            mesh.colors.is_some(),
+        );
+        Ok(Some((Arc::new(mesh), material, bounds)))
+    }
+
+    /// Component count and values of the accessor, normalized integers are scaled to [0, 1] or
+    /// [-1, 1] ranges
+    /// Accessors without a buffer view are zero, sparse accessors are read without their sparse
+    /// values
+    fn accessor(&self, index: usize) -> std::io::Result<(usize, Vec<f64>)> {
+        let a = item(&self.json, "accessors", index)?;
+        let count = a
+            .get("count")
+            .and_then(Json::as_usize)
+            .ok_or_else(|| invalid("glTF accessor without count"))?;
+        let components = match a.get("type").and_then(Json::as_str) {
+            Some("SCALAR") => 1,
+            Some("VEC2") => 2,
+            Some("VEC3") => 3,
+            Some("VEC4") | Some("MAT2") => 4,
+            Some("MAT3") => 9,
+            Some("MAT4") => 16,
+            _ => return Err(invalid("invalid glTF accessor type")),
+        };
+        let (size, max): (usize, f64) = match a.get("componentType").and_then(Json::as_usize) {
+            Some(5120) => (1, i8::MAX as f64),
+            Some(5121) => (1, u8::MAX as f64),
+            Some(5122) => (2, i16::MAX as f64),
+            Some(5123) => (2, u16::MAX as f64),
+            Some(5125) | Some(5126) => (4, 1.),
+            _ => return Err(invalid("invalid glTF accessor component type")),
+        };
+        let component_type = a.get("componentType").and_then(Json::as_usize);
+        let normalized = a.get("normalized").and_then(Json::as_bool).unwrap_or(false);
+        let length = count
+            .checked_mul(components)
+            .ok_or_else(|| invalid("glTF accessor too large"))?;
+        let Some(view) = a.get("bufferView").and_then(Json::as_usize) else {
+            return Ok((components, vec![0.; length]));
+        };
+        let (data, stride) = self.buffer_view(view)?;
+        let offset = a.get("byteOffset").and_then(Json::as_usize).unwrap_or(0);
+        let stride = stride.unwrap_or(size * components);
+        if stride < size * components {
+            return Err(invalid("glTF buffer view stride shorter than its elements"));
+        }
+        // the last element ends the read bytes, checked before allocating the values
+        let end = match count.checked_sub(1) {
+            Some(last) => last
+                .checked_mul(stride)
+                .and_then(|n| n.checked_add(offset))
+                .and_then(|n| n.checked_add(size * components)),
+            None => Some(0),
+        };
+        if end.is_none_or(|end| end > data.len()) {
+            return Err(invalid("glTF accessor out of its buffer view"));
+        }
+        let mut values = Vec::with_capacity(length);
+        for i in 0..count {
+            for c in 0..components {
+                let pos = offset + i * stride + c * size;
+                let b = &data[pos..pos + size];
+                let v = match component_type {
+                    Some(5120) => b[0] as i8 as f64,
+                    Some(5121) => b[0] as f64,
+                    Some(5122) => i16::from_le_bytes([b[0], b[1]]) as f64,
+                    Some(5123) => u16::from_le_bytes([b[0], b[1]]) as f64,
+                    Some(5125) => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    _ => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                };
+                values.push(if normalized { (v / max).max(-1.) } else { v });
+            }
+        }
+        Ok((components, values))
+    }
+
+    /// Data of the buffer view, along with its stride
+    fn buffer_view(&self, index: usize) -> std::io::Result<(&[u8], Option<usize>)> {
+        let view = item(&self.json, "bufferViews", index)?;
+        let buffer = view
+            .get("buffer")
+            .and_then(Json::as_usize)
+            .and_then(|b| self.buffers.get(b))
+            .ok_or_else(|| invalid("glTF buffer view without buffer"))?;
+        let offset = view.get("byteOffset").and_then(Json::as_usize).unwrap_or(0);
+        let length = view
+            .get("byteLength")
+            .and_then(Json::as_usize)
+            .ok_or_else(|| invalid("glTF buffer view without length"))?;
+        let data = offset
+            .checked_add(length)
+            .and_then(|end| buffer.get(offset..end))
+            .ok_or_else(|| invalid("glTF buffer view out of its buffer"))?;
+        Ok((data, view.get("byteStride").and_then(Json::as_usize)))
+    }
+
+    fn texture(&mut self, index: usize, dir: &Path) -> std::io::Result<Option<Arc<dyn Texture>>> {
+        let texture = item(&self.json, "textures", index)?;
+        let Some(source) = texture.get("source").and_then(Json::as_usize) else {
+            return Ok(None);
+        };
+        let image = item(&self.json, "images", source)?;
+        let data = match (
+            image.get("uri").and_then(Json::as_str),
+            image.get("bufferView").and_then(Json::as_usize),
+        ) {
+            (Some(uri), _) => load_uri(uri, dir)?,
+            (None, Some(view)) => self.buffer_view(view)?.0.to_vec(),
+            (None, None) => return Ok(None),
+        };
+        if !data.starts_with(b"\x89PNG") {
+            if !self.skipped_images.contains(&source) {
+                self.skipped_images.push(source);
+            }
+            return Ok(None);
+        }
+        let sampler = texture
+            .get("sampler")
+            .and_then(Json::as_usize)
+            .and_then(|s| array(&self.json, "samplers").get(s));
+        let sampler = |key: &str| sampler.and_then(|s| s.get(key)).and_then(Json::as_usize);
+        Ok(Some(Arc::new(ImageTexture {
+            image: Image::parse_png(&data)?,
+            wrap: match sampler("wrapS") {
+                Some(33071) => Wrap::Clamp,
+                Some(33648) => Wrap::Mirror,
+                _ => Wrap::Repeat,
+            },
+            filter: match sampler("magFilter") {
+                Some(9728) => Filter::Nearest,
+                _ => Filter::Bilinear,
+            },
+        })))
+    }
+
+    /// Renderer material of the glTF material, base color is multiplied by vertex colors of
+    /// primitives having them
+    fn material(&self, index: Option<usize>, vertex_colors: bool) -> Material {
+        let m = index
+            .and_then(|i| array(&self.json, "materials").get(i))
+            .unwrap_or(&Json::Null);
+        let pbr = m.get("pbrMetallicRoughness").unwrap_or(&Json::Null);
+        let color =
+            |json: Option<&Json>, default: Color| match json.and_then(Json::as_f32s).as_deref() {
+                Some([r, g, b, ..]) => Color::rgb(*r, *g, *b),
+                _ => default,
+            };
+        let texture = |info: Option<&Json>| {
+            info.and_then(|t| t.get("index"))
+                .and_then(Json::as_usize)
+                .and_then(|i| self.textures.get(i).cloned().flatten())
+        };
+        let factor = |json: &Json, key: &str| json.get(key).and_then(Json::as_f32).unwrap_or(1.);
+
+        let base = color(pbr.get("baseColorFactor"), Color::WHITE);
+        let mut maps: Vec<Arc<dyn Texture>> =
+            texture(pbr.get("baseColorTexture")).into_iter().collect();
+        if vertex_colors {
+            maps.push(Arc::new(VertexColor {
+                fallback: Color::WHITE,
+            }));
+        }
+        let color_map = maps.into_iter().reduce(multiply).map(|t| {
+            if base == Color::WHITE {
+                t
+            } else {
+                multiply(Arc::new(base), t)
+            }
+        });
+
+        let metallic = factor(pbr, "metallicFactor");
+        let metallic_roughness = texture(pbr.get("metallicRoughnessTexture"));
+        // roughness is in the green channel and metalness is in the blue channel
+        let roughness_map = metallic_roughness
+            .clone()
+            .map(|t| Arc::new(Channel::new(t, 1)) as Arc<dyn Texture>);
+        let (specularity, specularity_map) = match metallic_roughness {
+            Some(t) => (
+                DIELECTRIC_SPECULARITY,
+                Some(Arc::new(Channel {
+                    offset: 1.,
+                    scale: -metallic,
+                    ..Channel::new(t, 2)
+                }) as Arc<dyn Texture>),
+            ),
+            None => (DIELECTRIC_SPECULARITY * (1. - metallic), None),
+        };
+
+        let emissive = color(m.get("emissiveFactor"), Color::BLACK);
+        let strength = m
+            .get("extensions")
+            .and_then(|e| e.get("KHR_materials_emissive_strength"))
+            .map_or(1., |e| factor(e, "emissiveStrength"));
+        let peak = emissive.max();
+        let emission_map = (peak > 0.).then(|| {
+            let tint = Color::rgb(emissive.r / peak, emissive.g / peak, emissive.b / peak);
+            match texture(m.get("emissiveTexture")) {
+                Some(t) => multiply(Arc::new(tint), t),
+                None => Arc::new(tint) as Arc<dyn Texture>,
+            }
+        });
+
+        Material {
+            roughness: factor(pbr, "roughnessFactor"),
+            specularity,
+            color: base,
+            luminosity: peak * strength,
+            color_map,
+            roughness_map,
+            specularity_map,
+            emission_map,
+            normal_map: texture(m.get("normalTexture")),
+            ..Default::default()
+        }
+    }
+
+    /// Camera of the perspective glTF camera, looking along its node -z axis
+    fn perspective(
+        &self,
+        index: usize,
+        world: &Transform,
+        resolution: Vec3,
+    ) -> std::io::Result<Option<Camera>> {
+        let camera = item(&self.json, "cameras", index)?;
+        let Some(yfov) = camera
+            .get("perspective")
+            .and_then(|p| p.get("yfov"))
+            .and_then(Json::as_f32)
+        else {
+            return Ok(None);
+        };
+        let dir = world.vector(&Vec3::new(0., 0., -1.)).norm();
+        Ok(Some(perspective_camera(
+            world.point(&Vec3::zero()),
+            dir,
+            yfov,
+            resolution,
+        )))
+    }
+
+    /// Emissive object standing in for the punctual light
+    fn light(&self, index: usize, world: &Transform) -> std::io::Result<Object> {
+        let light = self
+            .json
+            .get("extensions")
+            .and_then(|e| e.get("KHR_lights_punctual"))
+            .and_then(|l| l.get("lights"))
+            .and_then(Json::as_array)
+            .and_then(|l| l.get(index))
+            .ok_or_else(|| invalid(&format!("missing glTF light {index}")))?;
+        let color = match light.get("color").and_then(Json::as_f32s).as_deref() {
+            Some([r, g, b, ..]) => Color::rgb(*r, *g, *b),
+            _ => Color::WHITE,
+        };
+        let intensity = light.get("intensity").and_then(Json::as_f32).unwrap_or(1.);
+        let material = |emission_unit: EmissionUnit, luminosity: f32| Material {
+            color,
+            luminosity,
+            emission_unit,
+            emission_map: Some(Arc::new(color)),
+            ..Default::default()
+        };
+        match light.get("type").and_then(Json::as_str) {
+            // illuminance in lux is luminance times the solid angle of the disk
+            Some("directional") => {
+                let dir = world.vector(&Vec3::new(0., 0., -1.)).norm();
+                let tan = SUN_ANGULAR_RADIUS.tan();
+                Ok(Object {
+                    shape: Box::new(Disk {
+                        center: dir.mul_n(-SUN_DISTANCE),
+                        normal: dir,
+                        radius: SUN_DISTANCE * tan,
+                    }),
+                    material: material(EmissionUnit::Nits, intensity / (PI * tan * tan)),
+                })
+            }
+            // intensity in candela is luminous flux per steradian
+            Some("point" | "spot") => Ok(Object {
+                shape: Box::new(Sphere {
+                    center: world.point(&Vec3::zero()),
+                    radius: LIGHT_RADIUS,
+                }),
+                material: material(EmissionUnit::Lumens, 4. * PI * intensity),
+            }),
+            _ => Err(invalid("invalid glTF light type")),
+        }
+    }
+}
+
+/// Local transform of the node by its matrix or by its translation, rotation and scale,
+/// `None` for singular transforms
+fn node_transform(node: &Json) -> Option<Transform> {
+    if let Some(values) = node.get("matrix").and_then(Json::as_f32s) {
+        let mut matrix = Matrix4::IDENTITY;
+        // glTF matrices are stored column by column
+        for (i, v) in values.iter().take(16).enumerate() {
+            matrix.m[i % 4][i / 4] = *v;
+        }
+        return Transform::from_matrix(matrix);
+    }
+    let vector = |key: &str, default: Vec3| match node.get(key).and_then(Json::as_f32s).as_deref() {
+        Some([x, y, z, ..]) => Vec3::new(*x, *y, *z),
+        _ => default,
+    };
+    let scale = vector("scale", Vec3::diag(1.));
+    if scale.x == 0. || scale.y == 0. || scale.z == 0. {
+        return None;
+    }
+    let rotation = match node.get("rotation").and_then(Json::as_f32s).as_deref() {
+        Some([x, y, z, w]) => quaternion(*x, *y, *z, *w),
+        _ => Transform::IDENTITY,
+    };
+    Some(
+        Transform::scale(scale)
+            .then(&rotation)
+            .then(&Transform::translate(vector("translation", Vec3::zero()))),
+    )
+}
+
+/// Rotation by the quaternion, normalized first
+fn quaternion(x: f32, y: f32, z: f32, w: f32) -> Transform {
+    let n = (x * x + y * y + z * z + w * w).sqrt();
+    if n == 0. {
+        return Transform::IDENTITY;
+    }
+    let (x, y, z, w) = (x / n, y / n, z / n, w / n);
+    let matrix = Matrix4 {
+        m: [
+            [
+                1. - 2. * (y * y + z * z),
+                2. * (x * y - z * w),
+                2. * (x * z + y * w),
+                0.,
+            ],
+            [
+                2. * (x * y + z * w),
+                1. - 2. * (x * x + z * z),
+                2. * (y * z - x * w),
+                0.,
+            ],
+            [
+                2. * (x * z - y * w),
+                2. * (y * z + x * w),
+                1. - 2. * (x * x + y * y),
+                0.,
+            ],
+            [0., 0., 0., 1.],
+        ],
+    };
+    Transform {
+        matrix,
+        inverse: matrix.transpose(),
+    }
+}
+
+fn perspective_camera(eye: Vec3, dir: Vec3, yfov: f32, resolution: Vec3) -> Camera {
+    let height = 2. * (yfov / 2.).tan();
+    Camera {
+        resolution,
+        viewport: Plane {
+            center: eye + dir,
+            size: Vec3::new(height * resolution.x / resolution.y, height, 0.),
+            dir,
+        },
+        focal_len: 1.,
+    }
+}
+
+/// Camera looking at the bounds from the front, with the bounding sphere filling its view
+fn default_camera(bounds: Option<[Vec3; 2]>, resolution: Vec3) -> Camera {
+    let [min, max] = bounds.unwrap_or([Vec3::diag(-1.), Vec3::diag(1.)]);
+    let center = (min + max).mul_n(0.5);
+    let radius = (max - min).mag().max(1e-3) / 2.;
+    let distance = radius / (DEFAULT_FOV / 2.).sin();
+    let dir = Vec3::new(0., 1., 0.);
+    perspective_camera(center - dir.mul_n(distance), dir, DEFAULT_FOV, resolution)
+}
+
+fn multiply(a: Arc<dyn Texture>, b: Arc<dyn Texture>) -> Arc<dyn Texture> {
+    Arc::new(Multiply { a, b })
+}
+
+/// Items of the array member, empty when the member is missing
+fn array<'a>(json: &'a Json, key: &str) -> &'a [Json] {
+    json.get(key).and_then(Json::as_array).unwrap_or(&[])
+}
+
+fn item<'a>(json: &'a Json, key: &str, index: usize) -> std::io::Result<&'a Json> {
+    array(json, key)
+        .get(index)
+        .ok_or_else(|| invalid(&format!("missing glTF {key} {index}")))
+}
+
+fn indices(json: &Json, key: &str) -> Vec<usize> {
+    array(json, key).iter().filter_map(Json::as_usize).collect()
+}
+
+/// Vectors of the VEC3 accessor
+fn vectors((components, values): (usize, Vec<f64>)) -> std::io::Result<Vec<Vec3>> {
+    if components != 3 {
+        return Err(invalid("unexpected glTF accessor type"));
+    }
+    Ok(values
+        .chunks_exact(3)
+        .map(|v| Vec3::new(v[0] as f32, v[1] as f32, v[2] as f32))
+        .collect())
+}
+
+/// Content of the base64 data URI or the file at the URI relative to `dir`
+fn load_uri(uri: &str, dir: &Path) -> std::io::Result<Vec<u8>> {
+    if let Some(data) = uri.strip_prefix("data:") {
+        let (_, encoded) = data
+            .split_once(";base64,")
+            .ok_or_else(|| invalid("unsupported glTF data URI"))?;
+        return base64(encoded);
+    }
+    read(dir.join(percent_decode(uri)))
+}
+
+fn percent_decode(uri: &str) -> PathBuf {
+    let bytes = uri.as_bytes();
+    let mut decoded = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                decoded.push(b);
+                i += 3;
+            }
+            (c, _) => {
+                decoded.push(c);
+                i += 1;
+            }
+        }
+    }
+    PathBuf::from(String::from_utf8_lossy(&decoded).into_owned())
+}
+
+fn base64(text: &str) -> std::io::Result<Vec<u8>> {
+    let mut data = vec![];
+    let (mut acc, mut bits) = (0u32, 0);
+    for c in text
+        .bytes()
+        .filter(|c| *c != b'=' && !c.is_ascii_whitespace())
+    {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return Err(invalid("invalid base64 data")),
+        };
+        acc = (acc << 6) | v as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            data.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    Ok(data)
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::emission::EmissionUnit;
+    use crate::gltf::{base64, parse_gltf};
+    use crate::math::approx_eq;
+    use crate::ray::Ray;
+    use crate::vec3::Vec3;
+
+    const RESOLUTION: Vec3 = Vec3 {
+        x: 200.,
+        y: 100.,
+        z: 0.,
+    };
+
+    /// Triangle positions followed by u16 indices, padded to 4 bytes
+    fn buffer() -> Vec<u8> {
+        let mut data = vec![];
+        for v in [0., 0., 0., 1., 0., 0., 0., 1., 0.] {
+            data.extend((v as f32).to_le_bytes());
+        }
+        for i in [0u16, 1, 2, 0] {
+            data.extend(i.to_le_bytes());
+        }
+        data
+    }
+
+    fn encode(data: &[u8]) -> String {
+        const CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut text = String::new();
+        for chunk in data.chunks(3) {
+            let b = [
+                chunk[0],
+                *chunk.get(1).unwrap_or(&0),
+                *chunk.get(2).unwrap_or(&0),
+            ];
+            let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+            for i in 0..4 {
+                text.push(if i <= chunk.len() {
+                    CHARS[(n >> (18 - 6 * i) & 63) as usize] as char
+                } else {
+                    '='
+                });
+            }
+        }
+        text
+    }
+
+    /// Buffer member holding `buffer` in its data URI
+    fn data_uri() -> String {
+        format!(
+            r#""uri": "data:application/octet-stream;base64,{}", "#,
+            encode(&buffer())
+        )
+    }
+
+    /// Triangle in the xy plane scaled by 2 and moved along x by its parent, with the camera
+    /// looking at it from +z and the point light above it
+    fn gltf(buffer: &str, camera: bool) -> String {
+        let camera = if camera {
+            r#"{"camera": 0, "translation": [0, 0, 10]}"#
+        } else {
+            "{}"
+        };
+        format!(
+            r#"{{
+            "asset": {{"version": "2.0"}},
+            "scene": 0,
+            "scenes": [{{"nodes": [0, 3]}}],
+            "nodes": [
+                {{"children": [1, 2], "translation": [1, 0, 0]}},
+                {{"mesh": 0, "scale": [2, 2, 2]}},
+                {camera},
+                {{"extensions": {{"KHR_lights_punctual": {{"light": 0}}}}, "translation": [0, 5, 0]}}
+            ],
+            "meshes": [{{"primitives": [{{
+                "attributes": {{"POSITION": 0}}, "indices": 1, "material": 0
+            }}]}}],
+            "materials": [{{
+                "pbrMetallicRoughness": {{
+                    "baseColorFactor": [1, 0, 0, 1], "metallicFactor": 0, "roughnessFactor": 0.3
+                }},
+                "emissiveFactor": [0, 0, 0]
+            }}],
+            "cameras": [{{"type": "perspective", "perspective": {{"yfov": 0.8, "znear": 0.1}}}}],
+            "extensions": {{"KHR_lights_punctual": {{"lights": [
+                {{"type": "point", "color": [1, 1, 0.5], "intensity": 20}}
+            ]}}}},
+            "buffers": [{{{buffer}"byteLength": 44}}],
+            "bufferViews": [
+                {{"buffer": 0, "byteLength": 36}},
+                {{"buffer": 0, "byteOffset": 36, "byteLength": 8}}
+            ],
+            "accessors": [
+                {{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3"}},
+                {{"bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR"}}
+            ]
+        }}"#
+        )
+    }
+
+    fn ray(start: Vec3) -> Ray {
+        Ray {
+            start,
+            dir: Vec3::new(0., 1., 0.),
+        }
+    }
+
+    #[test]
+    fn gltf_scene() {
+        // JPEG images are skipped and reported
+        let jpeg = r#""images": [{"uri": "data:image/jpeg;base64,/9j/"}], "#;
+        let json = gltf(&data_uri(), true).replace(
+            r#""scene": 0,"#,
+            &format!(r#""scene": 0, "textures": [{{"source": 0}}], {jpeg}"#),
+        );
+        let (scene, skipped_images) =
+            parse_gltf(json.as_bytes(), Path::new(""), RESOLUTION).unwrap();
+        assert_eq!(skipped_images, vec![0]);
+        assert_eq!(scene.objects.len(), 2);
+
+        // glTF y up space is turned to z up, so the triangle is in the xz plane
+        let hit = scene.objects[0]
+            .shape
+            .reflect(&ray(Vec3::new(1.5, -10., 0.5)))
+            .unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(1.5, 0., 0.5)), "{hit:?}");
+        assert!(hit.normal.approx_eq(&Vec3::new(0., -1., 0.)));
+        assert!(scene.objects[0]
+            .shape
+            .reflect(&ray(Vec3::new(2.5, -10., 1.5)))
+            .is_none());
+        let m = &scene.objects[0].material;
+        assert_eq!(m.color, crate::color::Color::RED);
+        assert!(approx_eq(m.roughness, 0.3) && approx_eq(m.specularity, 0.04));
+        assert_eq!(m.luminosity, 0.);
+
+        let c = scene.camera;
+        assert!(c.viewport.dir.approx_eq(&Vec3::new(0., 1., 0.)));
+        assert!(c.viewport.center.approx_eq(&Vec3::new(1., -9., 0.)));
+        assert!(approx_eq(c.viewport.size.y, 2. * 0.4f32.tan()));
+        assert!(approx_eq(c.viewport.size.x, 2. * c.viewport.size.y));
+
+        let light = &scene.objects[1];
+        assert!(light.shape.center().approx_eq(&Vec3::new(0., 0., 5.)));
+        assert_eq!(light.material.emission_unit, EmissionUnit::Lumens);
+        assert!(approx_eq(
+            light.material.luminosity,
+            80. * std::f32::consts::PI
+        ));
+    }
+
+    #[test]
+    fn glb() {
+        let mut json = gltf("", false).into_bytes();
+        json.resize(json.len().div_ceil(4) * 4, b' ');
+        let bin = buffer();
+        let mut glb = b"glTF".to_vec();
+        glb.extend(2u32.to_le_bytes());
+        glb.extend(((12 + 8 + json.len() + 8 + bin.len()) as u32).to_le_bytes());
+        glb.extend((json.len() as u32).to_le_bytes());
+        glb.extend(b"JSON");
+        glb.extend(&json);
+        glb.extend((bin.len() as u32).to_le_bytes());
+        glb.extend(b"BIN\0");
+        glb.extend(&bin);
+
+        let (scene, _) = parse_gltf(&glb, Path::new(""), RESOLUTION).unwrap();
+        assert!(scene.objects[0]
+            .shape
+            .reflect(&ray(Vec3::new(1.5, -10., 0.5)))
+            .is_some());
+        // without cameras, the scene is framed from the front
+        let c = scene.camera;
+        assert!(c.viewport.dir.approx_eq(&Vec3::new(0., 1., 0.)));
+        assert!(approx_eq(c.viewport.center.x, 2.) && approx_eq(c.viewport.center.z, 1.));
+        assert!(c.viewport.center.y < -1.);
+
+        assert!(parse_gltf(&glb[..30], Path::new(""), RESOLUTION).is_err());
+    }
+
+    #[test]
+    fn invalid_hierarchy() {
+        let parse = |from: &str, to: &str| {
+            let json = gltf(&data_uri(), false).replace(from, to);
+            parse_gltf(json.as_bytes(), Path::new(""), RESOLUTION)
+        };
+        assert!(parse("", "").is_ok());
+        let shared = parse(r#""children": [1, 2]"#, r#""children": [1, 1]"#);
+        assert!(shared.is_err());
+        let cycle = parse(r#""mesh": 0,"#, r#""mesh": 0, "children": [0],"#);
+        assert!(cycle.is_err());
+    }
+
+    #[test]
+    fn invalid_accessors() {
+        let parse = |from: &str, to: &str| {
+            let json = gltf(&data_uri(), true).replace(from, to);
+            parse_gltf(json.as_bytes(), Path::new(""), RESOLUTION)
+        };
+        // primitives without vertices are skipped, leaving the light alone
+        let scene = parse(
+            r#""count": 3, "type": "VEC3""#,
+            r#""count": 0, "type": "VEC3""#,
+        );
+        assert_eq!(scene.unwrap().0.objects.len(), 1);
+        for count in ["4", "1e300", "6148914691236517205"] {
+            let to = format!(r#""count": {count}, "type": "VEC3""#);
+            assert!(
+                parse(r#""count": 3, "type": "VEC3""#, &to).is_err(),
+                "{count}"
+            );
+        }
+        assert!(parse(
+            r#""byteLength": 36}"#,
+            r#""byteLength": 36, "byteStride": 4}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn decode_base64() {
+        assert_eq!(base64("aGVsbG8=").unwrap(), b"hello");
+        assert_eq!(base64(&encode(&buffer())).unwrap(), buffer());
+        assert!(base64("a?b").is_err());
+    }
+}
@@ -1,25 +1,172 @@
-use std::fs::write;
+use std::fs::{read, write};
+use std::io::{Error, ErrorKind};
+use std::path::Path;
 
 use crate::color::Color;
-use crate::vec3::Vec3;
 
 // TODO: add metadata (reflection_depth, pass_count)
 #[derive(Debug, Clone, PartialEq, PartialOrd, Default)]
 pub struct Image {
-    pub resolution: Vec3,
+    pub width: usize,
+    pub height: usize,
+    /// Pixel colors row by row, starting from the top left corner
     pub pixels: Vec<Color>,
 }
 
 impl Image {
+    pub fn new(width: usize, height: usize) -> Image {
+        Image {
+            width,
+            height,
+            pixels: vec![Color::BLACK; width * height],
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Color {
+        self.pixels[self.index(x, y)]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: Color) {
+        let i = self.index(x, y);
+        self.pixels[i] = color;
+    }
+
+    fn index(&self, x: usize, y: usize) -> usize {
+        assert!(
+            x < self.width && y < self.height,
+            "pixel ({x}, {y}) is out of {}x{} image",
+            self.width,
+            self.height
+        );
+        y * self.width + x
+    }
+
+    /// Load PPM or PNG image, depending on the file extension
+    pub fn load(path: &str) -> std::io::Result<Image> {
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("png") => Image::load_png(path),
+            Some("ppm" | "pgm") => Image::load_ppm(path),
+            _ => Err(invalid(&format!("unsupported image format of {path}"))),
+        }
+    }
+
+    /// Load binary (P5, P6) or plain (P2, P3) [PNM](https://netpbm.sourceforge.net/doc/ppm.html)
+    /// image
+    pub fn load_ppm(path: &str) -> std::io::Result<Image> {
+        Image::parse_ppm(&read(path)?)
+    }
+
+    pub fn parse_ppm(content: &[u8]) -> std::io::Result<Image> {
+        let mut pos = 0;
+        let mut token = || -> std::io::Result<String> {
+            loop {
+                match content.get(pos) {
+                    Some(b'#') => {
+                        while content.get(pos).is_some_and(|c| *c != b'\n') {
+                            pos += 1;
+                        }
+                    }
+                    Some(c) if c.is_ascii_whitespace() => pos += 1,
+                    Some(_) => break,
+                    None => return Err(invalid("unexpected end of PPM image")),
+                }
+            }
+            let start = pos;
+            while content.get(pos).is_some_and(|c| !c.is_ascii_whitespace()) {
+                pos += 1;
+            }
+            Ok(String::from_utf8_lossy(&content[start..pos]).to_string())
+        };
+        let number = |t: String| {
+            t.parse::<usize>()
+                .map_err(|_| invalid("invalid PPM number"))
+        };
+        let magic = token()?;
+        let channels = match magic.as_str() {
+            "P2" | "P5" => 1,
+            "P3" | "P6" => 3,
+            _ => return Err(invalid("unsupported PPM format")),
+        };
+        let width = number(token()?)?;
+        let height = number(token()?)?;
+        let max = number(token()?)?;
+        if max == 0 || max > 65535 {
+            return Err(invalid("invalid PPM max value"));
+        }
+        let too_large = || invalid("PPM image too large");
+        let count = width
+            .checked_mul(height)
+            .and_then(|n| n.checked_mul(channels))
+            .ok_or_else(too_large)?;
+        let values: Vec<usize> = if magic == "P2" || magic == "P3" {
+            (0..count)
+                .map(|_| token().and_then(number))
+                .collect::<std::io::Result<_>>()?
+        } else {
+            // single whitespace separates header from the binary data
+            let data = content.get(pos + 1..).unwrap_or_default();
+            let size = if max < 256 { 1 } else { 2 };
+            if data.len() < count.checked_mul(size).ok_or_else(too_large)? {
+                return Err(invalid("unexpected end of PPM image"));
+            }
+            data.chunks(size)
+                .take(count)
+                .map(|b| b.iter().fold(0, |v, b| v << 8 | *b as usize))
+                .collect()
+        };
+        if values.iter().any(|v| *v > max) {
+            return Err(invalid("PPM sample above max value"));
+        }
+        let v = |i: usize| values[i] as f32 / max as f32;
+        Ok(Image {
+            width,
+            height,
+            pixels: (0..width * height)
+                .map(|i| match channels {
+                    1 => Color::mono(v(i)),
+                    _ => Color::rgb(v(3 * i), v(3 * i + 1), v(3 * i + 2)),
+                })
+                .collect(),
+        })
+    }
+
+    /// Load PNG image, alpha channel is ignored
+    pub fn load_png(path: &str) -> std::io::Result<Image> {
+        Image::parse_png(&read(path)?)
+    }
+
+    pub fn parse_png(content: &[u8]) -> std::io::Result<Image> {
+        let mut decoder = png::Decoder::new(content);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().map_err(|e| invalid(&e.to_string()))?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader
+            .next_frame(&mut buf)
+            .map_err(|e| invalid(&e.to_string()))?;
+        let channels = info.color_type.samples();
+        let v = |i: usize| buf[i] as f32 / 255.;
+        Ok(Image {
+            width: info.width as usize,
+            height: info.height as usize,
+            pixels: buf[..info.buffer_size()]
+                .chunks(channels)
+                .enumerate()
+                .map(|(i, _)| {
+                    let i = i * channels;
+                    match channels {
+                        1 | 2 => Color::mono(v(i)),
+                        _ => Color::rgb(v(i), v(i + 1), v(i + 2)),
+                    }
+                })
+                .collect(),
+        })
+    }
+
     pub fn save_ppm(&self, path: &str) -> std::io::Result<()> {
         let mut content: Vec<u8> = vec![];
-        let header = format!(
-            "P6\n{w} {h}\n255\n",
-            w = self.resolution.x,
-            h = self.resolution.y
-        )
-        .as_bytes()
-        .to_vec();
+        let header = format!("P6\n{w} {h}\n255\n", w = self.width, h = self.height)
+            .as_bytes()
+            .to_vec();
         content.extend(header);
         for px in &self.pixels {
             content.extend([
@@ -30,32 +177,139 @@ impl Image {
         }
         write(path, content)
     }
+
+    /// Save unclamped pixel values as a [PFM](https://www.pauldebevec.com/Research/HDR/PFM/) image
+    pub fn save_pfm(&self, path: &str) -> std::io::Result<()> {
+        let mut content: Vec<u8> = vec![];
+        // negative scale marks little endian values
+        let header = format!("PF\n{w} {h}\n-1.0\n", w = self.width, h = self.height);
+        content.extend(header.as_bytes());
+        // rows are stored from the bottom to the top
+        for row in self.pixels.chunks(self.width.max(1)).rev() {
+            for px in row {
+                for v in [px.r, px.g, px.b] {
+                    content.extend(v.to_le_bytes());
+                }
+            }
+        }
+        write(path, content)
+    }
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
 }
 
 #[cfg(test)]
 mod test {
+    use std::env::temp_dir;
+    use std::fs::{read, remove_file};
+
     use crate::color::Color;
     use crate::image::Image;
-    use crate::vec3::Vec3;
+
+    #[test]
+    fn get_set() {
+        let mut i = Image::new(3, 2);
+        i.set(2, 0, Color::RED);
+        i.set(0, 1, Color::GREEN);
+        assert_eq!(i.get(2, 0), Color::RED);
+        assert_eq!(i.get(0, 1), Color::GREEN);
+        assert_eq!(i.pixels[2], Color::RED);
+        assert_eq!(i.pixels[3], Color::GREEN);
+    }
+
+    #[test]
+    #[should_panic]
+    fn get_out_of_bounds() {
+        Image::new(3, 2).get(0, 2);
+    }
 
     #[test]
     fn save_image() {
-        let r = Vec3::new(100., 100., 0.);
-        let w = r.x as i32;
-        let mut ps = vec![];
-        for x in 0..w as i32 {
-            for y in 0..r.y as i32 {
-                ps.push(Color {
-                    r: if (x + y) % 2 == 0 { 1. } else { 0. },
-                    g: 0.,
-                    b: 0.,
-                });
+        let (w, h) = (4, 3);
+        let mut i = Image::new(w, h);
+        for y in 0..h {
+            for x in 0..w {
+                i.set(x, y, Color::rgb(x as f32 / 255., y as f32 / 255., 0.));
             }
         }
-        let i = Image {
-            resolution: r,
-            pixels: ps,
+        let path = temp_dir().join(format!("sunny-{}-save_image.ppm", std::process::id()));
+        i.save_ppm(path.to_str().unwrap()).unwrap();
+
+        let content = read(&path).unwrap();
+        let header = b"P6\n4 3\n255\n";
+        assert!(content.starts_with(header));
+        let px = |x: usize, y: usize| {
+            let i = header.len() + (y * w + x) * 3;
+            (content[i], content[i + 1])
+        };
+        assert_eq!(px(3, 0), (3, 0));
+        assert_eq!(px(1, 2), (1, 2));
+        assert_eq!(content.len(), header.len() + w * h * 3);
+        remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn ppm_roundtrip() {
+        let mut i = Image::new(3, 2);
+        i.set(2, 0, Color::RED);
+        i.set(0, 1, Color::mono(128. / 255.));
+        let path = temp_dir().join(format!("sunny-{}-roundtrip.ppm", std::process::id()));
+        let path = path.to_str().unwrap();
+        i.save_ppm(path).unwrap();
+        assert_eq!(Image::load(path).unwrap(), i);
+        remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn parse_plain_ppm() {
+        let i = Image::parse_ppm(b"P3\n# comment\n2 1\n4\n4 0 0  0 2 4\n").unwrap();
+        assert_eq!(i.get(0, 0), Color::RED);
+        assert_eq!(i.get(1, 0), Color::rgb(0., 0.5, 1.));
+        let i = Image::parse_ppm(b"P2 1 2 10 5 10").unwrap();
+        assert_eq!(i.get(0, 1), Color::WHITE);
+        assert!(Image::parse_ppm(b"P3 2 1 4 4 0").is_err());
+        assert!(Image::parse_ppm(b"P2 1 1 4 5").is_err());
+        assert!(Image::parse_ppm(b"P6 4294967296 4294967296 255\n").is_err());
+    }
+
+    #[test]
+    fn load_png() {
+        let path = temp_dir().join(format!("sunny-{}-load.png", std::process::id()));
+        let mut encoder = png::Encoder::new(std::fs::File::create(&path).unwrap(), 2, 1);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer
+            .write_image_data(&[255, 0, 0, 255, 0, 51, 255, 0])
+            .unwrap();
+        writer.finish().unwrap();
+
+        let i = Image::load(path.to_str().unwrap()).unwrap();
+        assert_eq!((i.width, i.height), (2, 1));
+        assert_eq!(i.get(0, 0), Color::RED);
+        assert_eq!(i.get(1, 0), Color::rgb(0., 0.2, 1.));
+        remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn save_pfm() {
+        let mut i = Image::new(2, 2);
+        i.set(1, 0, Color::rgb(-1., 2., 0.5));
+        let path = temp_dir().join(format!("sunny-{}-save_pfm.pfm", std::process::id()));
+        i.save_pfm(path.to_str().unwrap()).unwrap();
+
+        let content = read(&path).unwrap();
+        let header = b"PF\n2 2\n-1.0\n";
+        assert!(content.starts_with(header));
+        assert_eq!(content.len(), header.len() + 2 * 2 * 3 * 4);
+        // top right pixel is the last one of the bottom to top rows
+        let v = |k: usize| {
+            let at = header.len() + (3 * 3 + k) * 4;
+            f32::from_le_bytes(content[at..at + 4].try_into().unwrap())
         };
-        i.save_ppm("data/test.ppm").ok();
+        assert_eq!((v(0), v(1), v(2)), (-1., 2., 0.5));
+        remove_file(path).unwrap();
     }
 }
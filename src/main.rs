@@ -1,34 +1,73 @@
 extern crate core;
 
+use std::env::args;
+use std::ops::ControlFlow;
+use std::process::exit;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::aov::Aov;
 use crate::camera::Camera;
+use crate::checkpoint::{load_checkpoint, save_checkpoint};
+use crate::cli::parse_args;
 use crate::color::Color;
+use crate::film::Film;
+use crate::gltf::load_gltf;
 use crate::material::Material;
 use crate::object::Object;
+use crate::progress::{CancelToken, Progress, RenderControl};
 use crate::scene::Scene;
+use crate::shape::infinite_plane::InfinitePlane;
 use crate::shape::plane::Plane;
 use crate::shape::sphere::Sphere;
 use crate::vec3::Vec3;
 
+pub mod aov;
 pub mod camera;
+pub mod checkpoint;
+pub mod cli;
 pub mod color;
+pub mod denoise;
+pub mod emission;
+pub mod film;
+pub mod gltf;
 pub mod image;
+pub mod json;
 pub mod material;
 pub mod math;
 pub mod object;
+pub mod progress;
 pub mod ray;
+pub mod sampler;
 pub mod scene;
+pub mod settings;
 pub mod shape;
+pub mod texture;
+pub mod tile;
+pub mod transform;
 pub mod vec3;
 
+// written as width / height of the frame
+#[allow(clippy::eq_op)]
 const ASP_RATIO: f32 = 16. / 16.;
 const WIDTH: f32 = 1080. * 2.0;
 const VP_WIDTH: f32 = 1.;
 
 const PIXEL_PASS_COUNT: usize = 100;
-const REFLECTION_DEPTH: usize = 6;
+const REFLECTION_DEPTH: usize = 32;
+const ROULETTE_DEPTH: usize = 3;
+const TILE_SIZE: usize = 32;
 const AMBIENT_COLOR: Color = Color::rgb(0.1, 0.1, 0.4);
 
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+const CHECKPOINT_PASS_SAMPLES: usize = 4;
+
 fn main() {
+    let args = parse_args(args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        exit(1);
+    });
+
     let c_pos = Vec3::new(-1., -1., 1.).norm().mul_n(5.);
     let c_dir = (Vec3::new(0., 0., 0.) - c_pos).norm();
     let camera = Camera {
@@ -52,20 +91,22 @@ fn main() {
             specularity: 0.6,
             color: Color::WHITE,
             luminosity: 0.,
+            ..Default::default()
         },
     };
     objects.push(floor);
 
     let super_floor = Object {
-        shape: Box::new(Sphere {
-            center: Vec3::new(0., 0., -10010.),
-            radius: 10000.,
+        shape: Box::new(InfinitePlane {
+            point: Vec3::new(0., 0., -10.),
+            normal: Vec3::new(0., 0., 1.),
         }),
         material: Material {
             roughness: 1.,
             specularity: 0.5,
             color: Color::mono(0.2),
             luminosity: 0.,
+            ..Default::default()
         },
     };
     objects.push(super_floor);
@@ -80,6 +121,7 @@ fn main() {
             specularity: 0.,
             color: Color::WHITE,
             luminosity: 5.0,
+            ..Default::default()
         },
     };
     objects.push(light);
@@ -94,6 +136,7 @@ fn main() {
             specularity: 0.,
             color: Color::RED,
             luminosity: 400.0,
+            ..Default::default()
         },
     };
     objects.push(light_a);
@@ -108,13 +151,120 @@ fn main() {
             specularity: 1.,
             color: Color::GREEN,
             luminosity: 0.,
+            ..Default::default()
         },
     };
     objects.push(o);
 
-    let scene = Scene { camera, objects };
-    let image = scene.render();
+    let scene = match &args.scene {
+        Some(path) => {
+            let (scene, skipped_images) = load_gltf(path, camera.resolution).unwrap_or_else(|e| {
+                eprintln!("failed to load {path}: {e}");
+                exit(1);
+            });
+            for image in skipped_images {
+                eprintln!("skipped image {image} of {path}, only PNG images are supported");
+            }
+            scene
+        }
+        None => Scene { camera, objects },
+    };
+    let start = Instant::now();
+    let last_report = Mutex::new(start);
+    let cancel = CancelToken::default();
+    let control = RenderControl {
+        cancel: cancel.clone(),
+        on_progress: Some(Box::new(|p: &Progress| {
+            if args.time_limit.is_some_and(|l| start.elapsed() >= l) {
+                cancel.cancel();
+            }
+            let mut last_report = last_report.lock().unwrap();
+            if last_report.elapsed() >= PROGRESS_INTERVAL || p.pixels_done == p.pixel_count {
+                eprint!("\r{p}");
+                *last_report = Instant::now();
+            }
+        })),
+    };
+    let (w, h) = (camera.resolution.x as usize, camera.resolution.y as usize);
+    let mut film = match &args.checkpoint {
+        Some(path) if args.resume => {
+            let film = load_checkpoint(path, &scene, &args.settings).unwrap_or_else(|e| {
+                eprintln!("unable to resume from {path}: {e}");
+                exit(1);
+            });
+            if let Some(aov) = args.aovs.iter().find(|a| film.aov(**a).is_none()) {
+                eprintln!("unable to resume from {path}: no {} AOV", aov.name());
+                exit(1);
+            }
+            film
+        }
+        _ => Film::new(w, h).with_aovs(&args.aovs),
+    };
+    if args.denoise.is_some() {
+        // denoiser feature buffers, made after resuming since they can't be added mid-render
+        if film.pixels.iter().any(|p| p.count > 0)
+            && (film.aov(Aov::Albedo).is_none() || film.aov(Aov::Normal).is_none())
+        {
+            eprintln!("unable to denoise, checkpoint has no albedo and normal AOVs");
+            exit(1);
+        }
+        film = film.with_aovs(&[Aov::Albedo, Aov::Normal]);
+    }
+    let save = |film: &Film| {
+        if let Some(path) = &args.checkpoint {
+            if let Err(e) = save_checkpoint(path, &scene, &args.settings, film) {
+                eprintln!("\nunable to save checkpoint {path}: {e}");
+            }
+        }
+    };
+    let output = |film: &Film| match args.settings.region {
+        Some(region) if args.crop => film.crop(&region.intersect(&film.area()).unwrap_or_default()),
+        _ => film.clone(),
+    };
+    let pass_samples = match (args.progressive, &args.checkpoint) {
+        (Some(pass_samples), _) => pass_samples,
+        // checkpoints are only made between passes
+        (None, Some(_)) => CHECKPOINT_PASS_SAMPLES,
+        (None, None) => usize::MAX,
+    };
+    let mut last_snapshot = Instant::now();
+    let mut last_checkpoint = Instant::now();
+    scene.render_progressive(&args.settings, &mut film, pass_samples, &control, |film| {
+        if args.progressive.is_some() && last_snapshot.elapsed() >= args.snapshot_interval {
+            output(film).image().save_ppm("data/scene.ppm").ok();
+            last_snapshot = Instant::now();
+        }
+        if last_checkpoint.elapsed() >= args.checkpoint_interval {
+            save(film);
+            last_checkpoint = Instant::now();
+        }
+        ControlFlow::Continue(())
+    });
+    eprintln!();
+    save(&film);
+    if cancel.is_cancelled() {
+        eprintln!("render cancelled after {:.1?}", start.elapsed());
+    }
+    let film = output(&film);
+    let image = match &args.denoise {
+        Some(denoise) => {
+            let albedo = film.aov_image(Aov::Albedo).unwrap();
+            let normal = film.aov_image(Aov::Normal).unwrap();
+            denoise::denoise(&film.image(), &albedo, &normal, denoise)
+        }
+        None => film.image(),
+    };
     image.save_ppm("data/scene.ppm").ok();
+    for aov in &args.aovs {
+        if let Some(image) = film.aov_image(*aov) {
+            image
+                .save_pfm(&format!("data/scene_{}.pfm", aov.name()))
+                .ok();
+        }
+    }
+    if let Some(path) = &args.heat_map {
+        film.heat_map().save_ppm(path).ok();
+    }
 }
 
 #[cfg(test)]
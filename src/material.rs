@@ -1,8 +1,14 @@
 use std::fmt::Debug;
+use std::sync::Arc;
 
 use crate::color::Color;
+use crate::emission::{blackbody, EmissionUnit};
+use crate::ray::Ray;
+use crate::shape::Hit;
+use crate::texture::Texture;
+use crate::vec3::Vec3;
 
-#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct Material {
     /// Determines how rays can be reflected from the surface
     /// When 0, acts as a perfect mirror
@@ -15,6 +21,263 @@ pub struct Material {
     pub specularity: f32,
 
     pub color: Color,
-    /// Surface light emitting property
+    /// Surface light emitting property, emission strength in `emission_unit`
     pub luminosity: f32,
+    pub emission_unit: EmissionUnit,
+    /// Blackbody temperature in Kelvin determining the emitted color
+    pub temperature: Option<f32>,
+
+    /// Texture replacing `color`
+    pub color_map: Option<Arc<dyn Texture>>,
+    /// Texture which lightness scales `roughness`
+    pub roughness_map: Option<Arc<dyn Texture>>,
+    /// Texture which lightness scales `specularity`
+    pub specularity_map: Option<Arc<dyn Texture>>,
+    /// Texture replacing the emitted color, tinted by the `temperature` color
+    pub emission_map: Option<Arc<dyn Texture>>,
+
+    /// Tangent space normal map, color channels in [0, 1] encode normal coordinates in [-1, 1]
+    /// along the tangent, bitangent and surface normal
+    pub normal_map: Option<Arc<dyn Texture>>,
+    /// Texture which lightness is the surface height, scaled by `bump_scale`
+    pub bump_map: Option<Arc<dyn Texture>>,
+    pub bump_scale: f32,
+}
+
+/// Material properties at the specific surface point
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Default)]
+pub struct Surface {
+    pub roughness: f32,
+    pub specularity: f32,
+    pub color: Color,
+    pub emission: Color,
+    /// Shading normal, perturbed by normal and bump maps
+    pub normal: Vec3,
+}
+
+impl Material {
+    /// Evaluate material textures at the hit point
+    pub fn surface(&self, hit: &Hit) -> Surface {
+        let scale = |v: f32, map: &Option<Arc<dyn Texture>>| match map {
+            Some(t) => (v * t.sample(hit).lightness()).clamp(0., 1.),
+            None => v,
+        };
+        let color = self
+            .color_map
+            .as_ref()
+            .map_or(self.color, |t| t.sample(hit));
+        Surface {
+            roughness: scale(self.roughness, &self.roughness_map),
+            specularity: scale(self.specularity, &self.specularity_map),
+            color,
+            emission: self.emission(hit, color),
+            normal: self.normal(hit),
+        }
+    }
+
+    /// Emitted color of the white radiance
+    fn emission(&self, hit: &Hit, color: Color) -> Color {
+        let map = self.emission_map.as_ref().map(|t| t.sample(hit));
+        match (map, self.temperature.map(blackbody)) {
+            (Some(map), Some(blackbody)) => map * blackbody,
+            (Some(c), None) | (None, Some(c)) => c,
+            (None, None) => color,
+        }
+    }
+
+    /// Shading normal, with bump map applied first and normal map applied in its tangent frame
+    fn normal(&self, hit: &Hit) -> Vec3 {
+        let mut normal = hit.normal;
+        if let Some(bump) = &self.bump_map {
+            normal = bump_normal(bump.as_ref(), self.bump_scale, hit);
+        }
+        if let Some(map) = &self.normal_map {
+            let c = map.sample(hit);
+            let (t, b, n) = hit.tangent_frame(&normal);
+            let mapped = t.mul_n(2. * c.r - 1.) + b.mul_n(2. * c.g - 1.) + n.mul_n(2. * c.b - 1.);
+            if mapped.mag() > 1e-6 {
+                normal = mapped.norm();
+            }
+        }
+        normal
+    }
+}
+
+/// Texture coordinate step of the height finite differences
+const BUMP_DELTA: f32 = 1e-3;
+
+/// Normal of the surface displaced along the normal by the height texture
+/// [ref](https://pbr-book.org/3ed-2018/Materials/Bump_Mapping)
+fn bump_normal(bump: &dyn Texture, scale: f32, hit: &Hit) -> Vec3 {
+    let height = |dp: Vec3, du: f32, dv: f32| {
+        let shifted = Hit {
+            ray: Ray {
+                start: hit.ray.start + dp,
+                dir: hit.ray.dir,
+            },
+            uv: (hit.uv.0 + du, hit.uv.1 + dv),
+            ..*hit
+        };
+        bump.sample(&shifted).lightness() * scale
+    };
+    let h = height(Vec3::zero(), 0., 0.);
+    let dhdu = (height(hit.dpdu.mul_n(BUMP_DELTA), BUMP_DELTA, 0.) - h) / BUMP_DELTA;
+    let dhdv = (height(hit.dpdv.mul_n(BUMP_DELTA), 0., BUMP_DELTA) - h) / BUMP_DELTA;
+    let dpdu = hit.dpdu + hit.normal.mul_n(dhdu);
+    let dpdv = hit.dpdv + hit.normal.mul_n(dhdv);
+    let n = dpdu.cross(&dpdv);
+    if n.mag() < 1e-12 {
+        return hit.normal;
+    }
+    let n = n.norm();
+    if n.dot(&hit.normal) < 0. {
+        -n
+    } else {
+        n
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::color::Color;
+    use crate::emission::blackbody;
+    use crate::image::Image;
+    use crate::material::Material;
+    use crate::ray::Ray;
+    use crate::shape::Hit;
+    use crate::texture::image::ImageTexture;
+    use crate::texture::procedural::{Gradient, Mapping, Pattern, Procedural, Space};
+    use crate::texture::vertex_color::VertexColor;
+    use crate::vec3::Vec3;
+
+    #[test]
+    fn surface() {
+        let mut image = Image::new(2, 1);
+        image.set(1, 0, Color::WHITE);
+        let texture = Arc::new(ImageTexture::new(image));
+        let m = Material {
+            roughness: 0.5,
+            specularity: 1.,
+            color: Color::RED,
+            color_map: Some(texture.clone()),
+            specularity_map: Some(texture),
+            ..Default::default()
+        };
+        let hit = |u: f32| Hit {
+            ray: Ray {
+                start: Vec3::new(0., 0., 0.),
+                dir: Vec3::new(1., 0., 0.),
+            },
+            normal: Vec3::new(1., 0., 0.),
+            uv: (u, 0.5),
+            ..Default::default()
+        };
+        let s = m.surface(&hit(0.25));
+        assert_eq!(s.color, Color::BLACK);
+        assert_eq!(s.specularity, 0.);
+        assert_eq!(s.roughness, 0.5);
+        let s = m.surface(&hit(0.75));
+        assert_eq!(s.color, Color::WHITE);
+        assert_eq!(s.emission, Color::WHITE);
+        assert_eq!(s.specularity, 1.);
+    }
+
+    fn plane_hit() -> Hit {
+        Hit {
+            ray: Ray {
+                start: Vec3::new(0.3, 0.4, 0.),
+                dir: Vec3::new(0., 0., 1.),
+            },
+            normal: Vec3::new(0., 0., 1.),
+            uv: (0.3, 0.4),
+            dpdu: Vec3::new(1., 0., 0.),
+            dpdv: Vec3::new(0., 1., 0.),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn emission() {
+        let warm = blackbody(3000.);
+        let m = Material {
+            color: Color::GREEN,
+            temperature: Some(3000.),
+            ..Default::default()
+        };
+        assert_eq!(m.surface(&plane_hit()).emission, warm);
+        assert_eq!(m.surface(&plane_hit()).color, Color::GREEN);
+        let screen = Material {
+            temperature: Some(3000.),
+            emission_map: Some(Arc::new(Color::RED)),
+            ..Default::default()
+        };
+        assert_eq!(
+            screen.surface(&plane_hit()).emission,
+            Color::rgb(warm.r, 0., 0.)
+        );
+    }
+
+    #[test]
+    fn vertex_color() {
+        let m = Material {
+            color: Color::WHITE,
+            color_map: Some(Arc::new(VertexColor {
+                fallback: Color::RED,
+            })),
+            ..Default::default()
+        };
+        assert_eq!(m.surface(&plane_hit()).color, Color::RED);
+        let colored = Hit {
+            color: Some(Color::BLUE),
+            ..plane_hit()
+        };
+        assert_eq!(m.surface(&colored).color, Color::BLUE);
+    }
+
+    #[test]
+    fn normal_map() {
+        let flat = Material {
+            normal_map: Some(Arc::new(Color::rgb(0.5, 0.5, 1.))),
+            ..Default::default()
+        };
+        assert!(flat
+            .surface(&plane_hit())
+            .normal
+            .approx_eq(&Vec3::new(0., 0., 1.)));
+        let tilted = Material {
+            normal_map: Some(Arc::new(Color::rgb(1., 0.5, 0.5))),
+            ..Default::default()
+        };
+        assert!(tilted
+            .surface(&plane_hit())
+            .normal
+            .approx_eq(&Vec3::new(1., 0., 0.)));
+    }
+
+    #[test]
+    fn bump_map() {
+        let ramp = Procedural {
+            mapping: Mapping {
+                space: Space::Uv,
+                ..Default::default()
+            },
+            ..Procedural::new(Pattern::Gradient(Gradient::Linear))
+        };
+        let m = Material {
+            bump_map: Some(Arc::new(ramp)),
+            bump_scale: 1.,
+            ..Default::default()
+        };
+        // height rising along u by 1 tilts the normal by 45 degrees against u
+        let n = m.surface(&plane_hit()).normal;
+        assert!(n.approx_eq(&Vec3::new(-1., 0., 1.).norm()), "{n:?}");
+        let flat = Material {
+            bump_map: Some(Arc::new(Color::WHITE)),
+            bump_scale: 1.,
+            ..Default::default()
+        };
+        assert_eq!(flat.surface(&plane_hit()).normal, Vec3::new(0., 0., 1.));
+    }
 }
@@ -6,3 +6,173 @@ pub fn approx_eq(a: f32, b: f32) -> bool {
     let e = 0.01;
     (a - b).abs() <= e
 }
+
+/// Coefficients this close to zero are treated as zero by polynomial solvers
+const SOLVER_EPSILON: f64 = 1e-12;
+
+fn is_zero(x: f64) -> bool {
+    x.abs() < SOLVER_EPSILON
+}
+
+/// Real roots of `a*x^2 + b*x + c`, in ascending order
+/// Degenerates to a linear equation when `a` is zero
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if is_zero(a) {
+        return if is_zero(b) { vec![] } else { vec![-c / b] };
+    }
+    let d = b * b - 4. * a * c;
+    if d < 0. {
+        return vec![];
+    }
+    // avoids cancellation of close values
+    let q = -0.5 * (b + d.sqrt().copysign(b));
+    let (x0, x1) = if is_zero(q) { (0., 0.) } else { (q / a, c / q) };
+    vec![x0.min(x1), x0.max(x1)]
+}
+
+/// Real roots of `a*x^3 + b*x^2 + c*x + d`, in ascending order
+/// [ref](https://github.com/erich666/GraphicsGems/blob/master/gems/Roots3And4.c)
+pub fn solve_cubic(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+    if is_zero(a) {
+        return solve_quadratic(b, c, d);
+    }
+    let (a, b, c) = (b / a, c / a, d / a);
+    // substitute x = y - a/3 to eliminate the quadratic term: y^3 + 3p*y + 2q = 0
+    let sq_a = a * a;
+    let p = (-sq_a / 3. + b) / 3.;
+    let q = (2. / 27. * a * sq_a - a * b / 3. + c) / 2.;
+    let cb_p = p * p * p;
+    let disc = q * q + cb_p;
+    let mut roots = if is_zero(disc) {
+        if is_zero(q) {
+            vec![0.]
+        } else {
+            let u = (-q).cbrt();
+            vec![2. * u, -u]
+        }
+    } else if disc < 0. {
+        let phi = (-q / (-cb_p).sqrt()).clamp(-1., 1.).acos() / 3.;
+        let t = 2. * (-p).sqrt();
+        vec![
+            t * phi.cos(),
+            -t * (phi + std::f64::consts::PI / 3.).cos(),
+            -t * (phi - std::f64::consts::PI / 3.).cos(),
+        ]
+    } else {
+        let sqrt_d = disc.sqrt();
+        vec![(sqrt_d - q).cbrt() - (sqrt_d + q).cbrt()]
+    };
+    for r in roots.iter_mut() {
+        *r -= a / 3.;
+    }
+    roots.sort_by(f64::total_cmp);
+    roots
+}
+
+/// Real roots of `a*x^4 + b*x^3 + c*x^2 + d*x + e`, in ascending order
+/// Solved by Ferrari's method, then every root is polished by Newton's iterations on the
+/// original polynomial to recover precision lost in the resolvent cubic
+/// [ref](https://github.com/erich666/GraphicsGems/blob/master/gems/Roots3And4.c)
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64, e: f64) -> Vec<f64> {
+    if is_zero(a) {
+        return solve_cubic(b, c, d, e);
+    }
+    let (qa, qb, qc, qd) = (b / a, c / a, d / a, e / a);
+    // substitute x = y - a/4 to eliminate the cubic term: y^4 + p*y^2 + q*y + r = 0
+    let sq_a = qa * qa;
+    let p = -3. / 8. * sq_a + qb;
+    let q = sq_a * qa / 8. - qa * qb / 2. + qc;
+    let r = -3. / 256. * sq_a * sq_a + sq_a * qb / 16. - qa * qc / 4. + qd;
+    let mut roots = if is_zero(r) {
+        let mut roots = solve_cubic(1., 0., p, q);
+        roots.push(0.);
+        roots
+    } else {
+        let z = solve_cubic(1., -p / 2., -r, r * p / 2. - q * q / 8.)[0];
+        let root = |x: f64| {
+            if is_zero(x) {
+                Some(0.)
+            } else if x > 0. {
+                Some(x.sqrt())
+            } else {
+                None
+            }
+        };
+        match (root(z * z - r), root(2. * z - p)) {
+            (Some(u), Some(v)) => {
+                let v = if q < 0. { -v } else { v };
+                let mut roots = solve_quadratic(1., v, z - u);
+                roots.extend(solve_quadratic(1., -v, z + u));
+                roots
+            }
+            _ => vec![],
+        }
+    };
+    let f = |x: f64| (((a * x + b) * x + c) * x + d) * x + e;
+    let df = |x: f64| ((4. * a * x + 3. * b) * x + 2. * c) * x + d;
+    for x in roots.iter_mut() {
+        *x -= qa / 4.;
+        for _ in 0..2 {
+            let slope = df(*x);
+            if slope != 0. {
+                *x -= f(*x) / slope;
+            }
+        }
+    }
+    roots.sort_by(f64::total_cmp);
+    roots
+}
+
+#[cfg(test)]
+mod test {
+    use crate::math::{solve_cubic, solve_quadratic, solve_quartic};
+
+    fn assert_roots(actual: Vec<f64>, expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len(), "{actual:?}");
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-6, "{actual:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn quadratic() {
+        assert_roots(solve_quadratic(1., -3., 2.), &[1., 2.]);
+        assert_roots(solve_quadratic(2., 0., 2.), &[]);
+        assert_roots(solve_quadratic(0., 2., -1.), &[0.5]);
+        assert_roots(solve_quadratic(1., 1e6, 1.), &[-999999.999999, -1e-6]);
+    }
+
+    #[test]
+    fn cubic() {
+        // (x - 1)(x + 2)(x - 3)
+        assert_roots(solve_cubic(1., -2., -5., 6.), &[-2., 1., 3.]);
+        assert_roots(solve_cubic(2., 0., 0., -16.), &[2.]);
+    }
+
+    #[test]
+    fn quartic() {
+        // (x - 1)(x + 1)(x - 2)(x - 3)
+        assert_roots(solve_quartic(1., -5., 5., 5., -6.), &[-1., 1., 2., 3.]);
+        // (x^2 + 1)(x - 4)(x + 0.5)
+        assert_roots(solve_quartic(1., -3.5, -1., -3.5, -2.), &[-0.5, 4.]);
+        assert_roots(solve_quartic(1., 0., 1., 0., 1.), &[]);
+        // torus-like coefficients with widely separated roots
+        // (x - 0.001)(x - 0.002)(x - 100)(x - 100.5)
+        let roots = [0.001, 0.002, 100., 100.5];
+        let (s1, s2, s3, s4) = (
+            roots.iter().sum::<f64>(),
+            0.001 * 0.002
+                + 0.001 * 100.
+                + 0.001 * 100.5
+                + 0.002 * 100.
+                + 0.002 * 100.5
+                + 100. * 100.5,
+            0.001 * 0.002 * 100.
+                + 0.001 * 0.002 * 100.5
+                + 0.001 * 100. * 100.5
+                + 0.002 * 100. * 100.5,
+            roots.iter().product::<f64>(),
+        );
+        assert_roots(solve_quartic(1., -s1, s2, -s3, s4), &roots);
+    }
+}
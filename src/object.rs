@@ -1,10 +1,43 @@
 use crate::material::Material;
 use std::fmt::Debug;
+use std::sync::Arc;
 
+use crate::shape::instance::Instance;
+use crate::shape::mesh::Mesh;
 use crate::shape::Shape;
+use crate::transform::Transform;
 
 #[derive(Debug)]
 pub struct Object {
     pub shape: Box<dyn Shape>,
     pub material: Material,
 }
+
+impl Object {
+    /// Object sharing the shape with other instances, placed by the transform
+    pub fn instance(shape: Arc<dyn Shape>, transform: Transform, material: Material) -> Object {
+        Object {
+            shape: Box::new(Instance { shape, transform }),
+            material,
+        }
+    }
+
+    /// Object with the shape moved from its object space by the transform
+    pub fn with_transform(self, transform: Transform) -> Object {
+        Object::instance(Arc::from(self.shape), transform, self.material)
+    }
+
+    /// Triangle mesh object loaded from the PLY or STL file
+    pub fn load_mesh(path: &str, material: Material) -> std::io::Result<Object> {
+        Ok(Object {
+            shape: Box::new(Mesh::load(path)?),
+            material,
+        })
+    }
+
+    /// Radiance of the white emission, converted from the material emission unit
+    pub fn radiance(&self) -> f32 {
+        let m = &self.material;
+        m.emission_unit.radiance(m.luminosity, self.shape.area())
+    }
+}
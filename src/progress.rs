@@ -0,0 +1,134 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Flag shared between the caller and render workers to abort a running render
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed)
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Progress of the current render pass
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Default)]
+pub struct Progress {
+    /// Index of the current pass, starting from 0
+    pub pass: usize,
+    pub tiles_done: usize,
+    pub tile_count: usize,
+    pub pixels_done: usize,
+    pub pixel_count: usize,
+    /// Number of samples taken since the start of the pass
+    pub samples: usize,
+    /// Time since the start of the pass
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    pub fn fraction(&self) -> f32 {
+        if self.pixel_count == 0 {
+            return 1.;
+        }
+        self.pixels_done as f32 / self.pixel_count as f32
+    }
+
+    pub fn samples_per_sec(&self) -> f32 {
+        self.samples as f32 / self.elapsed.as_secs_f32().max(f32::EPSILON)
+    }
+
+    /// Estimated time left until the pass is done
+    pub fn eta(&self) -> Option<Duration> {
+        if self.pixels_done == 0 {
+            return None;
+        }
+        let remaining = self.pixel_count.saturating_sub(self.pixels_done);
+        Some(
+            self.elapsed
+                .mul_f64(remaining as f64 / self.pixels_done as f64),
+        )
+    }
+}
+
+/// Single line progress bar
+impl Display for Progress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let width = 30;
+        let filled = ((self.fraction() * width as f32) as usize).min(width);
+        write!(
+            f,
+            "pass {} [{}{}] {:5.1}% tile {}/{} {:.2}M samples/s",
+            self.pass + 1,
+            "#".repeat(filled),
+            " ".repeat(width - filled),
+            self.fraction() * 100.,
+            self.tiles_done,
+            self.tile_count,
+            self.samples_per_sec() / 1e6,
+        )?;
+        if let Some(eta) = self.eta() {
+            let s = eta.as_secs();
+            write!(f, " ETA {}:{:02}:{:02}", s / 3600, s / 60 % 60, s % 60)?;
+        }
+        Ok(())
+    }
+}
+
+pub type ProgressCallback<'a> = Box<dyn Fn(&Progress) + Sync + 'a>;
+
+/// Hooks to observe and abort a running render
+#[derive(Default)]
+pub struct RenderControl<'a> {
+    pub cancel: CancelToken,
+    /// Called from render workers every time a tile is done
+    pub on_progress: Option<ProgressCallback<'a>>,
+}
+
+impl Debug for RenderControl<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RenderControl")
+            .field("cancel", &self.cancel)
+            .field("on_progress", &self.on_progress.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::progress::{CancelToken, Progress};
+
+    #[test]
+    fn cancel_shared() {
+        let token = CancelToken::default();
+        let worker = token.clone();
+        assert!(!worker.is_cancelled());
+        token.cancel();
+        assert!(worker.is_cancelled());
+    }
+
+    #[test]
+    fn eta() {
+        let p = Progress {
+            pass: 0,
+            tiles_done: 1,
+            tile_count: 4,
+            pixels_done: 25,
+            pixel_count: 100,
+            samples: 2500,
+            elapsed: Duration::from_secs(10),
+        };
+        assert_eq!(p.fraction(), 0.25);
+        assert_eq!(p.samples_per_sec(), 250.);
+        assert_eq!(p.eta(), Some(Duration::from_secs(30)));
+        assert!(p.to_string().ends_with("ETA 0:00:30"));
+    }
+}
@@ -1,6 +1,6 @@
 use crate::vec3::Vec3;
 
-#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Default)]
 pub struct Ray {
     pub start: Vec3,
     pub dir: Vec3,
@@ -0,0 +1,42 @@
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+
+/// Random number generator used for every sampling decision of a render
+pub type SampleRng = Pcg32;
+
+/// Create a generator for a single pixel sample
+/// The stream depends only on the seed, pixel index and sample index, so renders are
+/// reproducible regardless of how the work is scheduled between threads
+pub fn sample_rng(seed: u64, pixel: usize, sample: usize) -> SampleRng {
+    SampleRng::seed_from_u64(mix(mix(seed ^ mix(pixel as u64)) ^ sample as u64))
+}
+
+/// [SplitMix64](https://prng.di.unimi.it/splitmix64.c) finalizer
+fn mix(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod test {
+    use rand::Rng;
+
+    use crate::sampler::sample_rng;
+
+    #[test]
+    fn same_indices_same_stream() {
+        let a: u64 = sample_rng(7, 10, 3).gen();
+        let b: u64 = sample_rng(7, 10, 3).gen();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn different_indices_different_stream() {
+        let a: u64 = sample_rng(7, 10, 3).gen();
+        assert_ne!(a, sample_rng(8, 10, 3).gen::<u64>());
+        assert_ne!(a, sample_rng(7, 11, 3).gen::<u64>());
+        assert_ne!(a, sample_rng(7, 10, 4).gen::<u64>());
+    }
+}
@@ -1,12 +1,23 @@
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use rand::Rng;
 use rayon::prelude::*;
 
 use crate::camera::Camera;
 use crate::color::Color;
+use crate::film::{Film, PixelStats};
 use crate::image::Image;
 use crate::object::Object;
+use crate::progress::{CancelToken, Progress, RenderControl};
 use crate::ray::Ray;
+use crate::sampler::{sample_rng, SampleRng};
+use crate::settings::RenderSettings;
+use crate::shape::Hit;
+use crate::tile::{tiles, Tile};
 use crate::vec3::Vec3;
-use crate::{AMBIENT_COLOR, PIXEL_PASS_COUNT, REFLECTION_DEPTH};
+use crate::AMBIENT_COLOR;
 
 #[derive(Debug)]
 pub struct Scene {
@@ -14,8 +25,81 @@ pub struct Scene {
     pub objects: Vec<Object>,
 }
 
+/// Outcome of tracing a single light path
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Default)]
+pub struct Sample {
+    /// Light carried back along the path
+    pub color: Color,
+    /// Part of `color` coming from a light source or the sky seen directly
+    pub emission: Color,
+    /// Part of `color` coming after a single reflection
+    pub direct: Color,
+    /// Part of `color` coming after multiple reflections
+    pub indirect: Color,
+    /// First surface the path hits, `None` if it escapes the scene right away
+    pub hit: Option<SurfaceHit>,
+}
+
+impl Sample {
+    /// Record light reaching the camera after `depth` reflections
+    fn record(&mut self, depth: usize, color: Color) {
+        self.color = color;
+        match depth {
+            0 => self.emission = color,
+            1 => self.direct = color,
+            _ => self.indirect = color,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct SurfaceHit {
+    /// Index of the hit object in the scene
+    pub object: usize,
+    pub point: Vec3,
+    pub normal: Vec3,
+    /// Direction of the ray hitting the surface
+    pub dir: Vec3,
+    /// Distance traveled by the ray to the surface
+    pub dist: f32,
+    pub albedo: Color,
+}
+
 impl Scene {
-    pub fn render(&self) -> Image {
+    pub fn render(&self, settings: &RenderSettings) -> Image {
+        self.render_film(settings).image()
+    }
+
+    pub fn render_film(&self, settings: &RenderSettings) -> Film {
+        let mut film = Film::new(
+            self.camera.resolution.x as usize,
+            self.camera.resolution.y as usize,
+        );
+        self.render_progressive(
+            settings,
+            &mut film,
+            usize::MAX,
+            &RenderControl::default(),
+            |_| ControlFlow::Continue(()),
+        );
+        film
+    }
+
+    /// Render the scene in passes of at most `pass_samples` samples per pixel, but at least one,
+    /// accumulating them into the film
+    /// After each pass `snapshot` is called with the film, rendering stops early if it breaks or
+    /// the render is cancelled.
+    /// Samples already in the film are kept, so rendering continues where the film was left off
+    pub fn render_progressive(
+        &self,
+        settings: &RenderSettings,
+        film: &mut Film,
+        pass_samples: usize,
+        control: &RenderControl,
+        mut snapshot: impl FnMut(&Film) -> ControlFlow<()>,
+    ) {
+        // passes without samples would never finish the render
+        let pass_samples = pass_samples.max(1);
         let y = Vec3::new(0., 0., 1.)
             .norm()
             .cross(&self.camera.viewport.dir)
@@ -23,76 +107,472 @@ impl Scene {
         if y.mag() == 0. {
             panic!("don't look parallel to z axis, idk where is up");
         }
+        assert_eq!(
+            (film.width, film.height),
+            (
+                self.camera.resolution.x as usize,
+                self.camera.resolution.y as usize
+            ),
+            "film size does not match camera resolution"
+        );
 
-        let w = self.camera.resolution.x as i32;
-        let h = self.camera.resolution.y as i32;
-        let ps = (0..w * h)
-            .into_par_iter()
-            .map(|i| {
-                let y = i / w;
-                let x = (y / w) + (i % w);
-                (0..PIXEL_PASS_COUNT)
-                    .filter_map(|_| {
-                        let cr = self.camera.camera_ray(Vec3::new(x as f32, y as f32, 0.));
-                        self.ray_trace(&cr, 0)
-                    })
-                    .enumerate()
-                    .fold(Color::BLACK, |c1, (i, c2)| {
-                        let i_f = i as f32;
-                        let n_f = i_f + 1.;
-                        Color::rgb(
-                            (c1.r * i_f + c2.r) / n_f,
-                            (c1.g * i_f + c2.g) / n_f,
-                            (c1.b * i_f + c2.b) / n_f,
-                        )
-                    })
-            })
-            .collect();
-        Image {
-            resolution: self.camera.resolution,
-            pixels: ps,
+        let full = film.area();
+        let tiles = match settings.region.map_or(Some(full), |r| r.intersect(&full)) {
+            Some(area) => tiles(&area, settings.tile_size, settings.tile_order),
+            None => vec![],
+        };
+        let pixel_count = tiles.iter().map(Tile::pixel_count).sum();
+        for pass in 0.. {
+            let start = Instant::now();
+            let tiles_done = AtomicUsize::new(0);
+            let pixels_done = AtomicUsize::new(0);
+            let samples = AtomicUsize::new(0);
+            // bridging keeps workers picking up tiles in order
+            let rendered: Vec<(&Tile, Film, usize)> = tiles
+                .iter()
+                .par_bridge()
+                .map(|tile| {
+                    let mut tile_film = film.crop(tile);
+                    let (unfinished, tile_samples) = self.render_tile(
+                        tile,
+                        &mut tile_film,
+                        film.width,
+                        pass_samples,
+                        settings,
+                        &control.cancel,
+                    );
+                    if let Some(on_progress) = &control.on_progress {
+                        on_progress(&Progress {
+                            pass,
+                            tiles_done: tiles_done.fetch_add(1, Ordering::Relaxed) + 1,
+                            tile_count: tiles.len(),
+                            pixels_done: pixels_done
+                                .fetch_add(tile.pixel_count(), Ordering::Relaxed)
+                                + tile.pixel_count(),
+                            pixel_count,
+                            samples: samples.fetch_add(tile_samples, Ordering::Relaxed)
+                                + tile_samples,
+                            elapsed: start.elapsed(),
+                        });
+                    }
+                    (tile, tile_film, unfinished)
+                })
+                .collect();
+            let mut unfinished = 0;
+            for (tile, tile_film, tile_unfinished) in rendered {
+                film.set_tile(tile, &tile_film);
+                unfinished += tile_unfinished;
+            }
+            if snapshot(film).is_break() || control.cancel.is_cancelled() || unfinished == 0 {
+                return;
+            }
         }
     }
 
-    pub fn ray_trace(&self, ray: &Ray, depth: usize) -> Option<Color> {
-        if depth >= REFLECTION_DEPTH {
-            return Some(Color::BLACK);
+    /// Sample every pixel of the tile, `tile_film` is the film cropped to the tile
+    /// Returns the number of pixels with unspent sample budget and the number of samples taken
+    fn render_tile(
+        &self,
+        tile: &Tile,
+        tile_film: &mut Film,
+        width: usize,
+        pass_samples: usize,
+        settings: &RenderSettings,
+        cancel: &CancelToken,
+    ) -> (usize, usize) {
+        let mut unfinished = 0;
+        let mut samples = 0;
+        for j in 0..tile.pixel_count() {
+            if cancel.is_cancelled() {
+                return (unfinished + 1, samples);
+            }
+            let i = (tile.y + j / tile.width) * width + tile.x + j % tile.width;
+            let count = tile_film.pixels[j].count;
+            let px = pixel_position(i, width);
+            if !self.sample_pixel(i, px, tile_film, j, pass_samples, settings) {
+                unfinished += 1;
+            }
+            samples += tile_film.pixels[j].count - count;
         }
-        if let Some((Object { material: m, .. }, ref_n, ref_r)) = self.reflect(ray) {
-            if m.luminosity > 0. {
-                let angle = (-ray.dir).cos_angle(&ref_n) * 0.5 + 0.5;
-                return Some(m.color.with_lightness(m.luminosity * angle));
+        (unfinished, samples)
+    }
+
+    /// Add at most `samples` samples to the film pixel `j`, `i` is the image pixel index
+    /// Returns whether the sample budget of the pixel is spent
+    fn sample_pixel(
+        &self,
+        i: usize,
+        px: Vec3,
+        film: &mut Film,
+        j: usize,
+        samples: usize,
+        settings: &RenderSettings,
+    ) -> bool {
+        let budget = match settings.adaptive {
+            None => settings.pass_count,
+            Some(a) => a.max_samples,
+        };
+        let converged = |stats: &PixelStats| match settings.adaptive {
+            None => false,
+            Some(a) => stats.count >= a.min_samples && stats.error() <= a.threshold,
+        };
+        let target = film.pixels[j].count.saturating_add(samples).min(budget);
+        while film.pixels[j].count < target && !converged(&film.pixels[j]) {
+            let mut rng = sample_rng(settings.seed, i, film.pixels[j].count);
+            let cr = self.camera.camera_ray(px);
+            film.add_sample(j, &self.ray_trace(&cr, settings, &mut rng), &self.camera);
+        }
+        film.pixels[j].count >= budget || converged(&film.pixels[j])
+    }
+
+    /// Trace a light path starting with the ray
+    /// Path throughput is the fraction of light carried back to the camera along the path so far.
+    /// After `settings.roulette_depth` bounces paths are randomly terminated with probability
+    /// inverse to their throughput, and survivors are weighted up to keep the estimate unbiased
+    pub fn ray_trace(&self, ray: &Ray, settings: &RenderSettings, rng: &mut SampleRng) -> Sample {
+        let mut sample = Sample::default();
+        let mut ray = *ray;
+        let mut throughput = Color::WHITE;
+        for depth in 0..settings.reflection_depth {
+            let Some((id, o, hit)) = self.reflect(&ray) else {
+                let angle = (ray.dir).cos_angle(&self.camera.viewport.dir).clamp(0., 1.);
+                sample.record(depth, throughput * AMBIENT_COLOR.with_lightness(angle));
+                return sample;
             };
-            let next = Ray {
-                start: ref_r.start,
-                dir: (ref_r.dir + Vec3::rand().mul_n(m.roughness)).norm(),
+            let m = o.material.surface(&hit);
+            let hit = if m.normal != hit.normal {
+                hit.shade(&m.normal)
+            } else {
+                hit
+            };
+            let (ref_n, ref_r) = (hit.normal, hit.ray);
+            if depth == 0 {
+                sample.hit = Some(SurfaceHit {
+                    object: id,
+                    point: ref_r.start,
+                    normal: ref_n,
+                    dir: ray.dir,
+                    dist: ray.start.dist(&ref_r.start),
+                    albedo: m.color,
+                });
+            }
+            if o.material.luminosity > 0. {
+                let angle = (-ray.dir).cos_angle(&ref_n) * 0.5 + 0.5;
+                sample.record(
+                    depth,
+                    throughput * m.emission.with_lightness(o.radiance() * angle),
+                );
+                return sample;
             };
             // TODO: fresnel reflection
             // TODO: optimize inside-reflected rays
-            self.ray_trace(&next, depth + 1).map(|rc| {
-                m.color
-                    .mul_n(1. - m.specularity)
-                    .with_lightness(rc.lightness())
-                    + rc.mul_n(m.specularity)
-            })
-        } else {
-            let angle = (ray.dir).cos_angle(&self.camera.viewport.dir).clamp(0., 1.);
-            Some(AMBIENT_COLOR.with_lightness(angle))
+            throughput = throughput
+                * (m.color.mul_n(1. - m.specularity) + Color::WHITE.mul_n(m.specularity));
+            if depth + 1 >= settings.roulette_depth {
+                let survival = throughput.max().min(1.);
+                if rng.gen::<f32>() >= survival {
+                    return sample;
+                }
+                throughput = throughput.with_lightness(1. / survival);
+            }
+            ray = Ray {
+                start: ref_r.start,
+                dir: (ref_r.dir + Vec3::rand(rng).mul_n(m.roughness)).norm(),
+            };
         }
+        sample
     }
 
-    pub fn reflect(&self, ray: &Ray) -> Option<(&Object, Vec3, Ray)> {
+    /// Find the closest object hit by the ray, along with its index
+    pub fn reflect(&self, ray: &Ray) -> Option<(usize, &Object, Hit)> {
         let mut c_len = f32::MAX;
-        let mut closest: Option<(&Object, Vec3, Ray)> = None;
-        for o in &self.objects {
-            if let Some((reflection, norm)) = o.shape.reflect(ray) {
-                let len = ray.start.dist(&reflection.start);
+        let mut closest: Option<(usize, &Object, Hit)> = None;
+        for (i, o) in self.objects.iter().enumerate() {
+            if let Some(hit) = o.shape.reflect(ray) {
+                let len = ray.start.dist(&hit.ray.start);
                 if len < c_len {
                     c_len = len;
-                    closest = Some((o, norm, reflection));
+                    closest = Some((i, o, hit));
                 }
             }
         }
         closest
     }
 }
+
+/// Position of the pixel with the flat index `i` in an image of width `w`
+fn pixel_position(i: usize, w: usize) -> Vec3 {
+    Vec3::new((i % w) as f32, (i / w) as f32, 0.)
+}
+
+#[cfg(test)]
+mod test {
+    use std::ops::ControlFlow;
+    use std::sync::Mutex;
+
+    use crate::aov::Aov;
+    use crate::camera::Camera;
+    use crate::color::Color;
+    use crate::film::Film;
+    use crate::material::Material;
+    use crate::object::Object;
+    use crate::progress::{Progress, RenderControl};
+    use crate::sampler::sample_rng;
+    use crate::scene::{pixel_position, Scene};
+    use crate::settings::{AdaptiveSampling, RenderSettings};
+    use crate::shape::plane::Plane;
+    use crate::shape::sphere::Sphere;
+    use crate::tile::{Tile, TileOrder};
+    use crate::vec3::Vec3;
+
+    fn scene() -> Scene {
+        sized_scene(8, 8)
+    }
+
+    fn sized_scene(w: usize, h: usize) -> Scene {
+        let c_pos = Vec3::new(-1., -1., 1.).norm().mul_n(5.);
+        Scene {
+            camera: Camera {
+                resolution: Vec3::new(w as f32, h as f32, 0.),
+                viewport: Plane {
+                    center: c_pos,
+                    size: Vec3::new(1., 1., 0.),
+                    dir: (Vec3::zero() - c_pos).norm(),
+                },
+                focal_len: 1.5,
+            },
+            objects: vec![
+                Object {
+                    shape: Box::new(Sphere {
+                        center: Vec3::zero(),
+                        radius: 1.,
+                    }),
+                    material: Material {
+                        roughness: 0.5,
+                        specularity: 0.5,
+                        color: Color::GREEN,
+                        luminosity: 0.,
+                        ..Default::default()
+                    },
+                },
+                Object {
+                    shape: Box::new(Sphere {
+                        center: Vec3::new(-4., -4., 0.),
+                        radius: 2.,
+                    }),
+                    material: Material {
+                        roughness: 0.,
+                        specularity: 0.,
+                        color: Color::WHITE,
+                        luminosity: 5.,
+                        ..Default::default()
+                    },
+                },
+            ],
+        }
+    }
+
+    fn settings(seed: u64) -> RenderSettings {
+        RenderSettings {
+            seed,
+            pass_count: 4,
+            tile_size: 4,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn render_reproducible_across_thread_counts() {
+        let s = scene();
+        let render = |threads| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+                .install(|| s.render(&settings(1)))
+        };
+        assert_eq!(render(1), render(4));
+    }
+
+    #[test]
+    fn roulette_unbiased() {
+        let s = scene();
+        let ray = s.camera.camera_ray(Vec3::new(4., 4., 0.));
+        let mean = |roulette_depth| {
+            let settings = RenderSettings {
+                roulette_depth,
+                ..settings(0)
+            };
+            let n = 100000;
+            (0..n)
+                .map(|i| s.ray_trace(&ray, &settings, &mut sample_rng(0, 0, i)))
+                .map(|c| c.color.lightness())
+                .sum::<f32>()
+                / n as f32
+        };
+        let full = mean(settings(0).reflection_depth);
+        assert!((mean(0) - full).abs() / full < 0.05);
+    }
+
+    #[test]
+    fn adaptive_sampling() {
+        let settings = RenderSettings {
+            adaptive: Some(AdaptiveSampling {
+                min_samples: 4,
+                max_samples: 64,
+                threshold: 0.01,
+            }),
+            ..settings(0)
+        };
+        let film = scene().render_film(&settings);
+        let counts = film.pixels.iter().map(|p| p.count);
+        assert_eq!(counts.clone().min(), Some(4));
+        assert_eq!(counts.max(), Some(64));
+    }
+
+    #[test]
+    fn progressive_matches_full_render() {
+        let s = scene();
+        let mut film = Film::new(8, 8);
+        let mut passes = 0;
+        s.render_progressive(&settings(0), &mut film, 1, &Default::default(), |_| {
+            passes += 1;
+            ControlFlow::Continue(())
+        });
+        assert_eq!(passes, 4);
+        assert_eq!(film, s.render_film(&settings(0)));
+    }
+
+    #[test]
+    fn progressive_stop_early() {
+        let mut film = Film::new(8, 8);
+        let control = Default::default();
+        scene().render_progressive(&settings(0), &mut film, 3, &control, |_| {
+            ControlFlow::Break(())
+        });
+        assert!(film.pixels.iter().all(|p| p.count == 3));
+    }
+
+    #[test]
+    fn progress_and_cancel() {
+        let mut film = Film::new(8, 8);
+        let reports = Mutex::new(vec![]);
+        let control = RenderControl {
+            on_progress: Some(Box::new(|p: &Progress| reports.lock().unwrap().push(*p))),
+            ..Default::default()
+        };
+        scene().render_progressive(&settings(0), &mut film, 2, &control, |_| {
+            control.cancel.cancel();
+            ControlFlow::Continue(())
+        });
+        drop(control);
+        let reports = reports.into_inner().unwrap();
+        assert_eq!(reports.len(), 4);
+        let last = reports.iter().max_by_key(|p| p.pixels_done).unwrap();
+        assert_eq!(last.pixels_done, 64);
+        assert_eq!(last.samples, 128);
+        assert!(film.pixels.iter().all(|p| p.count == 2));
+    }
+
+    #[test]
+    fn tile_layout_independent() {
+        let s = scene();
+        let film = s.render_film(&settings(0));
+        for (tile_size, tile_order) in [(3, TileOrder::Spiral), (5, TileOrder::Hilbert)] {
+            let settings = RenderSettings {
+                tile_size,
+                tile_order,
+                ..settings(0)
+            };
+            assert_eq!(s.render_film(&settings), film);
+        }
+    }
+
+    #[test]
+    fn render_region() {
+        let s = scene();
+        let full = s.render_film(&settings(0));
+        let region = Tile {
+            x: 2,
+            y: 3,
+            width: 5,
+            height: 4,
+        };
+        let film = s.render_film(&RenderSettings {
+            region: Some(region),
+            ..settings(0)
+        });
+        assert_eq!(film.crop(&region), full.crop(&region));
+        let rendered = film.pixels.iter().filter(|p| p.count > 0).count();
+        assert_eq!(rendered, region.pixel_count());
+    }
+
+    #[test]
+    fn pixel_position_row_major() {
+        assert_eq!(pixel_position(0, 3), Vec3::new(0., 0., 0.));
+        assert_eq!(pixel_position(2, 3), Vec3::new(2., 0., 0.));
+        assert_eq!(pixel_position(3, 3), Vec3::new(0., 1., 0.));
+        assert_eq!(pixel_position(20, 3), Vec3::new(2., 6., 0.));
+    }
+
+    #[test]
+    fn render_non_square() {
+        let settings = RenderSettings {
+            pass_count: 1,
+            ..settings(0)
+        };
+        for (w, h) in [(7, 3), (3, 7)] {
+            let s = sized_scene(w, h);
+            let image = s.render(&settings);
+            assert_eq!((image.width, image.height), (w, h));
+            for y in 0..h {
+                for x in 0..w {
+                    let ray = s.camera.camera_ray(Vec3::new(x as f32, y as f32, 0.));
+                    let mut rng = sample_rng(settings.seed, y * w + x, 0);
+                    assert_eq!(
+                        image.get(x, y),
+                        s.ray_trace(&ray, &settings, &mut rng).color
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn render_aovs() {
+        let s = scene();
+        let mut film = Film::new(8, 8).with_aovs(&Aov::ALL);
+        s.render_progressive(
+            &settings(0),
+            &mut film,
+            usize::MAX,
+            &Default::default(),
+            |_| ControlFlow::Continue(()),
+        );
+        let aov = |aov| film.aov_image(aov).unwrap();
+        let (id, albedo, depth) = (aov(Aov::ObjectId), aov(Aov::Albedo), aov(Aov::Depth));
+        let (emission, direct, indirect) =
+            (aov(Aov::Emission), aov(Aov::Direct), aov(Aov::Indirect));
+        let image = film.image();
+        for i in 0..image.pixels.len() {
+            let parts = emission.pixels[i].r + direct.pixels[i].r + indirect.pixels[i].r;
+            assert!((parts - image.pixels[i].r).abs() < 1e-5);
+            match id.pixels[i].r {
+                0. => assert_eq!(depth.pixels[i], Color::BLACK),
+                1. => {
+                    assert_eq!(albedo.pixels[i], Color::GREEN);
+                    assert!(depth.pixels[i].r > 5. && depth.pixels[i].r < 6.5);
+                }
+                v => panic!("unexpected object id {v}"),
+            }
+        }
+        assert!(id.pixels.contains(&Color::mono(1.)));
+        assert_eq!(film.image(), s.render(&settings(0)));
+    }
+
+    #[test]
+    fn render_depends_on_seed() {
+        let s = scene();
+        assert_ne!(s.render(&settings(1)), s.render(&settings(2)));
+    }
+}
@@ -0,0 +1,54 @@
+use crate::tile::{Tile, TileOrder};
+use crate::{PIXEL_PASS_COUNT, REFLECTION_DEPTH, ROULETTE_DEPTH, TILE_SIZE};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RenderSettings {
+    /// Seed all pixel samples are derived from
+    pub seed: u64,
+    /// Number of samples traced per pixel, unless adaptive sampling is used
+    pub pass_count: usize,
+    pub adaptive: Option<AdaptiveSampling>,
+    /// Maximum number of bounces of a light path
+    pub reflection_depth: usize,
+    /// Number of bounces after which paths are subject to russian roulette termination
+    pub roulette_depth: usize,
+    /// Width and height of square tiles the image is rendered in
+    pub tile_size: usize,
+    pub tile_order: TileOrder,
+    /// Pixel-space rectangle to render, pixels outside of it receive no samples
+    pub region: Option<Tile>,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        RenderSettings {
+            seed: 0,
+            pass_count: PIXEL_PASS_COUNT,
+            adaptive: None,
+            reflection_depth: REFLECTION_DEPTH,
+            roulette_depth: ROULETTE_DEPTH,
+            tile_size: TILE_SIZE,
+            tile_order: TileOrder::default(),
+            region: None,
+        }
+    }
+}
+
+/// Keep sampling a pixel until its estimated error drops below the threshold
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AdaptiveSampling {
+    pub min_samples: usize,
+    pub max_samples: usize,
+    /// Maximum acceptable standard error of the pixel lightness
+    pub threshold: f32,
+}
+
+impl Default for AdaptiveSampling {
+    fn default() -> Self {
+        AdaptiveSampling {
+            min_samples: 16,
+            max_samples: PIXEL_PASS_COUNT * 4,
+            threshold: 0.005,
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use std::f32::consts::PI;
+
+use crate::ray::Ray;
+use crate::shape::{
+    azimuth, closest_hit, components, cylinder_roots, spans, sphere_roots, Crossing, Frame, Hit,
+    Shape,
+};
+use crate::vec3::Vec3;
+
+/// Segment between `a` and `b` inflated by the `radius`
+/// Texture coordinates are the angle around the segment and the distance along it
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Capsule {
+    pub a: Vec3,
+    pub b: Vec3,
+    pub radius: f32,
+}
+
+impl Capsule {
+    /// Crossings of the ray with the surface
+    fn crossings(&self, ray: &Ray) -> Vec<Crossing> {
+        let len = self.a.dist(&self.b);
+        let frame = Frame::new(self.a, (self.b - self.a).norm());
+        let local = frame.local_ray(ray);
+        let r = self.radius;
+        let hit = |t: f32| {
+            let p = local.with_param(t);
+            let normal = p - Vec3::new(0., 0., p.z.clamp(0., len));
+            let (u, dpdu) = azimuth(&p);
+            let along = Vec3::new(-normal.y, normal.x, 0.);
+            let dpdv = if along.mag() > 1e-6 {
+                normal.norm().cross(&along.norm())
+            } else {
+                Vec3::new(0., 0., 1.)
+            };
+            Crossing {
+                t,
+                normal,
+                uv: (u, ((p.z + r) / (len + 2. * r)).clamp(0., 1.)),
+                dpdu,
+                dpdv: dpdv.mul_n(len + 2. * r),
+            }
+        };
+        let side = cylinder_roots(&local, r)
+            .into_iter()
+            .filter(|t| (0. ..=len).contains(&local.with_param(*t).z));
+        let bottom = sphere_roots(&local, &Vec3::zero(), r)
+            .into_iter()
+            .filter(|t| local.with_param(*t).z < 0.);
+        let top = sphere_roots(&local, &Vec3::new(0., 0., len), r)
+            .into_iter()
+            .filter(|t| local.with_param(*t).z > len);
+        frame.crossings(side.chain(bottom).chain(top).map(hit))
+    }
+}
+
+impl Shape for Capsule {
+    fn reflect(&self, ray: &Ray) -> Option<Hit> {
+        closest_hit(ray, self.crossings(ray))
+    }
+
+    fn center(&self) -> Vec3 {
+        (self.a + self.b).mul_n(0.5)
+    }
+
+    fn area(&self) -> f32 {
+        2. * PI * self.radius * self.a.dist(&self.b) + 4. * PI * self.radius * self.radius
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.extend(components(&[self.a, self.b]));
+        out.push(self.radius);
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<(Crossing, Crossing)> {
+        spans(self.crossings(ray))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::math::approx_eq;
+    use crate::ray::Ray;
+    use crate::shape::capsule::Capsule;
+    use crate::shape::Shape;
+    use crate::vec3::Vec3;
+
+    fn capsule() -> Capsule {
+        Capsule {
+            a: Vec3::new(0., 0., 0.),
+            b: Vec3::new(2., 0., 0.),
+            radius: 0.5,
+        }
+    }
+
+    #[test]
+    fn reflect_side() {
+        let r = Ray {
+            start: Vec3::new(1., 0., 3.),
+            dir: Vec3::new(0., 0., -1.),
+        };
+        let hit = capsule().reflect(&r).unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(1., 0., 0.5)));
+        assert!(hit.normal.approx_eq(&Vec3::new(0., 0., 1.)));
+        assert!(approx_eq(hit.uv.1, 0.5));
+    }
+
+    #[test]
+    fn reflect_ends() {
+        let r = Ray {
+            start: Vec3::new(5., 0., 0.),
+            dir: Vec3::new(-1., 0., 0.),
+        };
+        let hit = capsule().reflect(&r).unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(2.5, 0., 0.)));
+        assert!(hit.normal.approx_eq(&Vec3::new(1., 0., 0.)));
+        assert!(approx_eq(hit.uv.1, 1.));
+
+        let r = Ray {
+            start: Vec3::new(-0.3, 0., 3.),
+            dir: Vec3::new(0., 0., -1.),
+        };
+        let hit = capsule().reflect(&r).unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(-0.3, 0., 0.4)));
+        assert!(hit.normal.approx_eq(&Vec3::new(-0.6, 0., 0.8)));
+    }
+
+    #[test]
+    fn miss() {
+        let r = Ray {
+            start: Vec3::new(2.55, 0., 3.),
+            dir: Vec3::new(0., 0., -1.),
+        };
+        assert!(capsule().reflect(&r).is_none());
+    }
+}
@@ -0,0 +1,148 @@
+use std::f32::consts::PI;
+
+use crate::math::solve_quadratic;
+use crate::ray::Ray;
+use crate::shape::{azimuth, cap_hit, closest_hit, components, spans, Crossing, Frame, Hit, Shape};
+use crate::vec3::Vec3;
+
+/// Cone with the `base` disk center and the apex at `height` along the unit `axis`
+/// Side texture coordinates are the angle around the axis and the height
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Cone {
+    pub base: Vec3,
+    pub axis: Vec3,
+    /// Base radius
+    pub radius: f32,
+    pub height: f32,
+    /// Whether the base is closed with a disk
+    pub capped: bool,
+}
+
+impl Cone {
+    /// Crossings of the ray with the surface
+    fn crossings(&self, ray: &Ray) -> Vec<Crossing> {
+        let frame = Frame::new(self.base, self.axis);
+        let local = frame.local_ray(ray);
+        let (s, d, h) = (local.start, local.dir, self.height);
+        // x^2 + y^2 = k^2 * (h - z)^2
+        let k2 = (self.radius / h).powi(2);
+        let roots = solve_quadratic(
+            (d.x * d.x + d.y * d.y - k2 * d.z * d.z) as f64,
+            2. * (s.x * d.x + s.y * d.y + k2 * (h - s.z) * d.z) as f64,
+            (s.x * s.x + s.y * s.y - k2 * (h - s.z).powi(2)) as f64,
+        );
+        let side = roots.into_iter().map(|t| {
+            let t = t as f32;
+            let p = local.with_param(t);
+            if p.z < 0. || p.z > h {
+                return None;
+            }
+            let (u, dpdu) = azimuth(&p);
+            let (sin, cos) = (2. * PI * u).sin_cos();
+            let normal = Vec3::new(p.x, p.y, k2 * (h - p.z));
+            Some(Crossing {
+                t,
+                normal: if normal.mag() > 1e-6 {
+                    normal
+                } else {
+                    Vec3::new(0., 0., 1.)
+                },
+                uv: (u, p.z / h),
+                dpdu,
+                dpdv: Vec3::new(-self.radius * cos, -self.radius * sin, h),
+            })
+        });
+        let cap = self
+            .capped
+            .then(|| cap_hit(&local, 0., self.radius, -1.))
+            .flatten();
+        frame.crossings(side.chain([cap]).flatten())
+    }
+}
+
+impl Shape for Cone {
+    fn reflect(&self, ray: &Ray) -> Option<Hit> {
+        closest_hit(ray, self.crossings(ray))
+    }
+
+    fn center(&self) -> Vec3 {
+        self.base + self.axis.mul_n(self.height / 4.)
+    }
+
+    fn area(&self) -> f32 {
+        let r = self.radius;
+        let cap = if self.capped { PI * r * r } else { 0. };
+        PI * r * (r * r + self.height * self.height).sqrt() + cap
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.extend(components(&[self.base, self.axis]));
+        out.extend([self.radius, self.height, self.capped as u8 as f32]);
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<(Crossing, Crossing)> {
+        if !self.capped {
+            return vec![];
+        }
+        spans(self.crossings(ray))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::math::approx_eq;
+    use crate::ray::Ray;
+    use crate::shape::cone::Cone;
+    use crate::shape::Shape;
+    use crate::vec3::Vec3;
+
+    fn cone() -> Cone {
+        Cone {
+            base: Vec3::new(0., 0., 0.),
+            axis: Vec3::new(0., 0., 1.),
+            radius: 1.,
+            height: 1.,
+            capped: true,
+        }
+    }
+
+    #[test]
+    fn reflect_side() {
+        let r = Ray {
+            start: Vec3::new(-5., 0., 0.5),
+            dir: Vec3::new(1., 0., 0.),
+        };
+        let hit = cone().reflect(&r).unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(-0.5, 0., 0.5)));
+        assert!(hit.normal.approx_eq(&Vec3::new(-1., 0., 1.).norm()));
+        assert!(hit.ray.dir.approx_eq(&Vec3::new(0., 0., 1.)));
+        assert!(approx_eq(hit.uv.1, 0.5));
+        assert!(approx_eq(hit.dpdv.dot(&hit.normal), 0.));
+    }
+
+    #[test]
+    fn reflect_base() {
+        let r = Ray {
+            start: Vec3::new(0.2, 0.2, -3.),
+            dir: Vec3::new(0., 0., 1.),
+        };
+        let hit = cone().reflect(&r).unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(0.2, 0.2, 0.)));
+        assert!(hit.normal.approx_eq(&Vec3::new(0., 0., -1.)));
+    }
+
+    #[test]
+    fn miss() {
+        let r = Ray {
+            start: Vec3::new(-5., 0.8, 0.5),
+            dir: Vec3::new(1., 0., 0.),
+        };
+        assert!(cone().reflect(&r).is_none());
+        // mirrored nappe of the cone above the apex is not part of the shape
+        let r = Ray {
+            start: Vec3::new(-5., 0., 1.5),
+            dir: Vec3::new(1., 0., 0.),
+        };
+        assert!(cone().reflect(&r).is_none());
+    }
+}
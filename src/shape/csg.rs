@@ -0,0 +1,229 @@
+use crate::ray::Ray;
+use crate::shape::{closest_hit, Crossing, Hit, Shape};
+use crate::vec3::Vec3;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CsgOp {
+    /// Inside of either shape
+    Union,
+    /// Inside of both shapes
+    Intersection,
+    /// Inside of the first shape and outside of the second one
+    Difference,
+}
+
+impl CsgOp {
+    fn inside(&self, a: bool, b: bool) -> bool {
+        match self {
+            CsgOp::Union => a || b,
+            CsgOp::Intersection => a && b,
+            CsgOp::Difference => a && !b,
+        }
+    }
+}
+
+/// Constructive solid geometry node, combining solids of child shapes
+/// Children must be closed shapes, open surfaces have no inside and are ignored
+#[derive(Debug)]
+pub struct Csg {
+    pub op: CsgOp,
+    pub a: Box<dyn Shape>,
+    pub b: Box<dyn Shape>,
+}
+
+impl Shape for Csg {
+    fn reflect(&self, ray: &Ray) -> Option<Hit> {
+        closest_hit(ray, self.spans(ray).into_iter().flat_map(|(a, b)| [a, b]))
+    }
+
+    fn center(&self) -> Vec3 {
+        match self.op {
+            CsgOp::Difference => self.a.center(),
+            _ => (self.a.center() + self.b.center()).mul_n(0.5),
+        }
+    }
+
+    /// Upper bound, the area of both children
+    fn area(&self) -> f32 {
+        self.a.area() + self.b.area()
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.push(self.op as u8 as f32);
+        self.a.fingerprint(out);
+        self.b.fingerprint(out);
+    }
+
+    /// Sweep along the ray through crossings of both children, tracking whether the ray is
+    /// inside each of them, and keep intervals where the operation is inside
+    fn spans(&self, ray: &Ray) -> Vec<(Crossing, Crossing)> {
+        let mut events: Vec<(Crossing, bool)> = vec![];
+        for (shape, is_a) in [(&self.a, true), (&self.b, false)] {
+            for (enter, exit) in shape.spans(ray) {
+                events.push((enter, is_a));
+                events.push((exit, is_a));
+            }
+        }
+        events.sort_by(|(x, _), (y, _)| x.t.total_cmp(&y.t));
+        let (mut in_a, mut in_b) = (false, false);
+        let mut enter = None;
+        let mut spans = vec![];
+        for (mut c, is_a) in events {
+            let was_inside = self.op.inside(in_a, in_b);
+            if is_a {
+                in_a = !in_a;
+            } else {
+                in_b = !in_b;
+                if self.op == CsgOp::Difference {
+                    // surface of the subtracted shape faces into its solid
+                    c.normal = -c.normal;
+                }
+            }
+            match (was_inside, self.op.inside(in_a, in_b)) {
+                (false, true) => enter = Some(c),
+                (true, false) => spans.extend(enter.take().map(|e| (e, c))),
+                _ => {}
+            }
+        }
+        spans
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::ray::Ray;
+    use crate::shape::csg::{Csg, CsgOp};
+    use crate::shape::cuboid::Cuboid;
+    use crate::shape::cylinder::Cylinder;
+    use crate::shape::instance::Instance;
+    use crate::shape::sphere::Sphere;
+    use crate::shape::Shape;
+    use crate::transform::Transform;
+    use crate::vec3::Vec3;
+
+    /// Two unit spheres overlapping between x of -0.5 and 0.5
+    fn csg(op: CsgOp) -> Csg {
+        Csg {
+            op,
+            a: Box::new(Sphere {
+                center: Vec3::new(-0.5, 0., 0.),
+                radius: 1.,
+            }),
+            b: Box::new(Sphere {
+                center: Vec3::new(0.5, 0., 0.),
+                radius: 1.,
+            }),
+        }
+    }
+
+    fn ray(x: f32) -> Ray {
+        Ray {
+            start: Vec3::new(x, 0., 0.),
+            dir: Vec3::new(1., 0., 0.),
+        }
+    }
+
+    fn span_bounds(csg: &Csg, r: &Ray) -> Vec<(f32, f32)> {
+        csg.spans(r)
+            .iter()
+            .map(|(a, b)| (r.with_param(a.t).x, r.with_param(b.t).x))
+            .collect()
+    }
+
+    #[test]
+    fn spans() {
+        let r = ray(-5.);
+        let eq = |a: Vec<(f32, f32)>, b: &[(f32, f32)]| {
+            assert_eq!(a.len(), b.len(), "{a:?}");
+            for (x, y) in a.iter().zip(b) {
+                assert!(
+                    (x.0 - y.0).abs() < 1e-4 && (x.1 - y.1).abs() < 1e-4,
+                    "{a:?}"
+                );
+            }
+        };
+        eq(span_bounds(&csg(CsgOp::Union), &r), &[(-1.5, 1.5)]);
+        eq(span_bounds(&csg(CsgOp::Intersection), &r), &[(-0.5, 0.5)]);
+        eq(span_bounds(&csg(CsgOp::Difference), &r), &[(-1.5, -0.5)]);
+    }
+
+    #[test]
+    fn reflect() {
+        // lens, hit by the second sphere surface
+        let hit = csg(CsgOp::Intersection).reflect(&ray(-5.)).unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(-0.5, 0., 0.)));
+        assert!(hit.normal.approx_eq(&Vec3::new(-1., 0., 0.)));
+
+        // carved surface of the difference faces out of the subtracted sphere
+        let r = Ray {
+            start: Vec3::new(5., 0., 0.),
+            dir: Vec3::new(-1., 0., 0.),
+        };
+        let hit = csg(CsgOp::Difference).reflect(&r).unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(-0.5, 0., 0.)), "{hit:?}");
+        assert!(hit.normal.approx_eq(&Vec3::new(1., 0., 0.)));
+
+        // ray starting inside the union hits its far side
+        let hit = csg(CsgOp::Union).reflect(&ray(0.)).unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(1.5, 0., 0.)));
+        assert!(hit.normal.approx_eq(&Vec3::new(-1., 0., 0.)));
+    }
+
+    #[test]
+    fn hollow() {
+        let shell = Csg {
+            op: CsgOp::Difference,
+            a: Box::new(Cuboid::new(Vec3::zero(), Vec3::diag(2.))),
+            b: Box::new(Sphere {
+                center: Vec3::zero(),
+                radius: 0.9,
+            }),
+        };
+        let nested = Csg {
+            op: CsgOp::Union,
+            a: Box::new(shell),
+            b: Box::new(Sphere {
+                center: Vec3::zero(),
+                radius: 0.5,
+            }),
+        };
+        let bounds = span_bounds(&nested, &ray(-5.));
+        assert_eq!(bounds.len(), 3, "{bounds:?}");
+        assert!((bounds[1].0 + 0.5).abs() < 1e-4 && (bounds[1].1 - 0.5).abs() < 1e-4);
+        // ray through the corner misses the cavity
+        let r = Ray {
+            start: Vec3::new(-5., 0.95, 0.95),
+            dir: Vec3::new(1., 0., 0.),
+        };
+        assert_eq!(span_bounds(&nested, &r).len(), 1);
+    }
+
+    #[test]
+    fn drilled() {
+        let hole = Cylinder {
+            base: Vec3::new(0., 0., -2.),
+            axis: Vec3::new(0., 0., 1.),
+            radius: 0.5,
+            height: 4.,
+            capped: true,
+        };
+        let drilled = Csg {
+            op: CsgOp::Difference,
+            a: Box::new(Cuboid::new(Vec3::zero(), Vec3::diag(2.))),
+            b: Box::new(Instance {
+                shape: Arc::new(hole),
+                transform: Transform::translate(Vec3::new(0.5, 0., 0.)),
+            }),
+        };
+        let bounds = span_bounds(&drilled, &ray(-5.));
+        assert_eq!(bounds.len(), 1, "{bounds:?}");
+        assert!((bounds[0].0 + 1.).abs() < 1e-4 && bounds[0].1.abs() < 1e-4);
+        let down = Ray {
+            start: Vec3::new(0.5, 0., 5.),
+            dir: Vec3::new(0., 0., -1.),
+        };
+        assert!(drilled.reflect(&down).is_none());
+    }
+}
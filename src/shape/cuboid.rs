@@ -0,0 +1,280 @@
+use crate::ray::Ray;
+use crate::shape::{closest_hit, components, Crossing, Frame, Hit, Shape, HIT_EPSILON};
+use crate::vec3::Vec3;
+
+/// Axis-aligned box between `min` and `max` corners
+/// Every face has its own texture coordinates, spanning the face along the next two axes
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Cuboid {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Cuboid {
+    pub fn new(center: Vec3, size: Vec3) -> Cuboid {
+        Cuboid {
+            min: center - size.mul_n(0.5),
+            max: center + size.mul_n(0.5),
+        }
+    }
+
+    /// Ray parameters of entering and leaving the box, along with the axes of the faces
+    /// [ref](https://tavianator.com/2011/ray_box.html)
+    pub fn slabs(&self, ray: &Ray) -> Option<((f32, usize), (f32, usize))> {
+        let mut near = (f32::MIN, 0);
+        let mut far = (f32::MAX, 0);
+        for a in 0..3 {
+            let (start, dir) = (axis(&ray.start, a), axis(&ray.dir, a));
+            let (min, max) = (axis(&self.min, a), axis(&self.max, a));
+            if dir == 0. {
+                if start < min || start > max {
+                    return None;
+                }
+                continue;
+            }
+            let (t0, t1) = ((min - start) / dir, (max - start) / dir);
+            let (t0, t1) = (t0.min(t1), t0.max(t1));
+            if t0 > near.0 {
+                near = (t0, a);
+            }
+            if t1 < far.0 {
+                far = (t1, a);
+            }
+        }
+        (near.0 <= far.0 && far.0 >= HIT_EPSILON).then_some((near, far))
+    }
+
+    /// Entry and exit crossings of the ray
+    fn crossings(&self, ray: &Ray) -> Option<(Crossing, Crossing)> {
+        let ((t0, a0), (t1, a1)) = self.slabs(ray)?;
+        Some((
+            self.crossing(ray, t0, a0, -axis(&ray.dir, a0).signum()),
+            self.crossing(ray, t1, a1, axis(&ray.dir, a1).signum()),
+        ))
+    }
+
+    /// Crossing of the face perpendicular to the axis `a`, facing the `side` along it
+    fn crossing(&self, ray: &Ray, t: f32, a: usize, side: f32) -> Crossing {
+        let (b, c) = ((a + 1) % 3, (a + 2) % 3);
+        let size = self.max - self.min;
+        let rel = ray.with_param(t) - self.min;
+        Crossing {
+            t,
+            normal: unit(a).mul_n(side),
+            uv: (
+                (axis(&rel, b) / axis(&size, b)).clamp(0., 1.),
+                (axis(&rel, c) / axis(&size, c)).clamp(0., 1.),
+            ),
+            dpdu: unit(b).mul_n(axis(&size, b)),
+            dpdv: unit(c).mul_n(axis(&size, c)),
+        }
+    }
+}
+
+impl Shape for Cuboid {
+    fn reflect(&self, ray: &Ray) -> Option<Hit> {
+        let (near, far) = self.crossings(ray)?;
+        // ray starting inside hits the box from within
+        closest_hit(ray, [near, far])
+    }
+
+    fn center(&self) -> Vec3 {
+        (self.min + self.max).mul_n(0.5)
+    }
+
+    fn area(&self) -> f32 {
+        let s = self.max - self.min;
+        2. * (s.x * s.y + s.y * s.z + s.z * s.x)
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.extend(components(&[self.min, self.max]));
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<(Crossing, Crossing)> {
+        self.crossings(ray).into_iter().collect()
+    }
+}
+
+/// Cuboid rotated around its center by `rotation` angles around x, y and z axes, in radians
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct OrientedCuboid {
+    pub cuboid: Cuboid,
+    pub rotation: Vec3,
+}
+
+impl OrientedCuboid {
+    /// Frame at the cuboid center with axes along the rotated cuboid edges
+    fn frame(&self) -> Frame {
+        let rotate = |a: usize| {
+            unit(a)
+                .rotate_x(self.rotation.x)
+                .rotate_y(self.rotation.y)
+                .rotate_z(self.rotation.z)
+        };
+        Frame {
+            origin: self.cuboid.center(),
+            x: rotate(0),
+            y: rotate(1),
+            z: rotate(2),
+        }
+    }
+
+    /// Cuboid in the frame, centered at its origin
+    fn local(&self) -> Cuboid {
+        let c = self.cuboid.center();
+        Cuboid {
+            min: self.cuboid.min - c,
+            max: self.cuboid.max - c,
+        }
+    }
+}
+
+impl Shape for OrientedCuboid {
+    fn reflect(&self, ray: &Ray) -> Option<Hit> {
+        let frame = self.frame();
+        let (near, far) = self.local().crossings(&frame.local_ray(ray))?;
+        closest_hit(ray, [frame.crossing(&near), frame.crossing(&far)])
+    }
+
+    fn center(&self) -> Vec3 {
+        self.cuboid.center()
+    }
+
+    fn area(&self) -> f32 {
+        self.cuboid.area()
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        self.cuboid.fingerprint(out);
+        out.extend(components(&[self.rotation]));
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<(Crossing, Crossing)> {
+        let frame = self.frame();
+        let spans = self.local().spans(&frame.local_ray(ray));
+        spans
+            .iter()
+            .map(|(a, b)| (frame.crossing(a), frame.crossing(b)))
+            .collect()
+    }
+}
+
+fn axis(v: &Vec3, a: usize) -> f32 {
+    match a {
+        0 => v.x,
+        1 => v.y,
+        _ => v.z,
+    }
+}
+
+fn unit(a: usize) -> Vec3 {
+    match a {
+        0 => Vec3::new(1., 0., 0.),
+        1 => Vec3::new(0., 1., 0.),
+        _ => Vec3::new(0., 0., 1.),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f32::consts::PI;
+
+    use crate::math::approx_eq;
+    use crate::ray::Ray;
+    use crate::shape::cuboid::{Cuboid, OrientedCuboid};
+    use crate::shape::Shape;
+    use crate::vec3::Vec3;
+
+    fn cuboid() -> Cuboid {
+        Cuboid::new(Vec3::new(0., 0., 0.), Vec3::new(2., 4., 6.))
+    }
+
+    #[test]
+    fn reflect_faces() {
+        let cases = [
+            (
+                Vec3::new(-5., 0.5, 1.),
+                Vec3::new(1., 0., 0.),
+                Vec3::new(-1., 0., 0.),
+            ),
+            (
+                Vec3::new(0.5, 5., 1.),
+                Vec3::new(0., -1., 0.),
+                Vec3::new(0., 1., 0.),
+            ),
+            (
+                Vec3::new(0.5, 1., -5.),
+                Vec3::new(0., 0., 1.),
+                Vec3::new(0., 0., -1.),
+            ),
+        ];
+        for (start, dir, normal) in cases {
+            let hit = cuboid().reflect(&Ray { start, dir }).unwrap();
+            assert!(hit.normal.approx_eq(&normal), "{hit:?}");
+            assert!(hit.ray.dir.approx_eq(&-dir), "{hit:?}");
+            assert!(approx_eq(
+                hit.ray.start.dot(&normal.abs()).abs(),
+                cuboid().max.dot(&normal.abs())
+            ));
+        }
+    }
+
+    #[test]
+    fn uv() {
+        let r = Ray {
+            start: Vec3::new(-5., 1., 1.5),
+            dir: Vec3::new(1., 0., 0.),
+        };
+        let hit = cuboid().reflect(&r).unwrap();
+        assert!(approx_eq(hit.uv.0, 0.75) && approx_eq(hit.uv.1, 0.75));
+        assert!(hit.dpdu.approx_eq(&Vec3::new(0., 4., 0.)));
+    }
+
+    #[test]
+    fn reflect_inside() {
+        let r = Ray {
+            start: Vec3::zero(),
+            dir: Vec3::new(0., 0., 1.),
+        };
+        let hit = cuboid().reflect(&r).unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(0., 0., 3.)));
+        assert!(hit.normal.approx_eq(&Vec3::new(0., 0., -1.)));
+    }
+
+    #[test]
+    fn miss() {
+        let r = Ray {
+            start: Vec3::new(-5., 3., 0.),
+            dir: Vec3::new(1., 0., 0.),
+        };
+        assert!(cuboid().reflect(&r).is_none());
+        let behind = Ray {
+            start: Vec3::new(-5., 0., 0.),
+            dir: Vec3::new(-1., 0., 0.),
+        };
+        assert!(cuboid().reflect(&behind).is_none());
+        assert!(approx_eq(cuboid().area(), 88.));
+    }
+
+    #[test]
+    fn oriented() {
+        let o = OrientedCuboid {
+            cuboid: Cuboid::new(Vec3::new(1., 0., 0.), Vec3::new(2., 2., 2.)),
+            rotation: Vec3::new(0., 0., PI / 4.),
+        };
+        let r = Ray {
+            start: Vec3::new(-5., 0., 0.),
+            dir: Vec3::new(1., 0., 0.),
+        };
+        let hit = o.reflect(&r).unwrap();
+        // rotated box is hit by the edge at the half diagonal from the center
+        assert!(
+            hit.ray
+                .start
+                .approx_eq(&Vec3::new(1. - 2f32.sqrt(), 0., 0.)),
+            "{hit:?}"
+        );
+        assert!(approx_eq(hit.normal.z, 0.) && hit.normal.x < 0.);
+    }
+}
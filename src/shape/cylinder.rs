@@ -0,0 +1,142 @@
+use std::f32::consts::PI;
+
+use crate::ray::Ray;
+use crate::shape::{
+    azimuth, cap_hit, closest_hit, components, cylinder_roots, spans, Crossing, Frame, Hit, Shape,
+};
+use crate::vec3::Vec3;
+
+/// Cylinder standing on the `base` center along the unit `axis`
+/// Side texture coordinates are the angle around the axis and the height
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Cylinder {
+    pub base: Vec3,
+    pub axis: Vec3,
+    pub radius: f32,
+    pub height: f32,
+    /// Whether the ends are closed with disks
+    pub capped: bool,
+}
+
+impl Cylinder {
+    /// Crossings of the ray with the surface
+    fn crossings(&self, ray: &Ray) -> Vec<Crossing> {
+        let frame = Frame::new(self.base, self.axis);
+        let local = frame.local_ray(ray);
+        let side = cylinder_roots(&local, self.radius).into_iter().map(|t| {
+            let p = local.with_param(t);
+            if p.z < 0. || p.z > self.height {
+                return None;
+            }
+            let (u, dpdu) = azimuth(&p);
+            Some(Crossing {
+                t,
+                normal: p.with_z(0.),
+                uv: (u, p.z / self.height),
+                dpdu,
+                dpdv: Vec3::new(0., 0., self.height),
+            })
+        });
+        let caps = [(0., -1.), (self.height, 1.)].map(|(z, side)| {
+            self.capped
+                .then(|| cap_hit(&local, z, self.radius, side))
+                .flatten()
+        });
+        frame.crossings(side.chain(caps).flatten())
+    }
+}
+
+impl Shape for Cylinder {
+    fn reflect(&self, ray: &Ray) -> Option<Hit> {
+        closest_hit(ray, self.crossings(ray))
+    }
+
+    fn center(&self) -> Vec3 {
+        self.base + self.axis.mul_n(self.height / 2.)
+    }
+
+    fn area(&self) -> f32 {
+        let caps = if self.capped {
+            2. * PI * self.radius * self.radius
+        } else {
+            0.
+        };
+        2. * PI * self.radius * self.height + caps
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.extend(components(&[self.base, self.axis]));
+        out.extend([self.radius, self.height, self.capped as u8 as f32]);
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<(Crossing, Crossing)> {
+        if !self.capped {
+            return vec![];
+        }
+        spans(self.crossings(ray))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::math::approx_eq;
+    use crate::ray::Ray;
+    use crate::shape::cylinder::Cylinder;
+    use crate::shape::Shape;
+    use crate::vec3::Vec3;
+
+    fn cylinder(capped: bool) -> Cylinder {
+        Cylinder {
+            base: Vec3::new(0., 0., 0.),
+            axis: Vec3::new(0., 0., 1.),
+            radius: 1.,
+            height: 2.,
+            capped,
+        }
+    }
+
+    #[test]
+    fn reflect_side() {
+        let r = Ray {
+            start: Vec3::new(-5., 0., 1.5),
+            dir: Vec3::new(1., 0., 0.),
+        };
+        let hit = cylinder(true).reflect(&r).unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(-1., 0., 1.5)));
+        assert!(hit.normal.approx_eq(&Vec3::new(-1., 0., 0.)));
+        assert!(approx_eq(hit.uv.0, 0.5) && approx_eq(hit.uv.1, 0.75));
+    }
+
+    #[test]
+    fn caps() {
+        let r = Ray {
+            start: Vec3::new(0.5, 0., 5.),
+            dir: Vec3::new(0., 0., -1.),
+        };
+        let hit = cylinder(true).reflect(&r).unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(0.5, 0., 2.)));
+        assert!(hit.normal.approx_eq(&Vec3::new(0., 0., 1.)));
+        // through the open end onto the inner side of the tube
+        let r = Ray {
+            start: Vec3::new(0.5, 0., 2.5),
+            dir: Vec3::new(0.3, 0., -1.).norm(),
+        };
+        let hit = cylinder(false).reflect(&r).unwrap();
+        assert!(approx_eq(hit.ray.start.x, 1.));
+        assert!(hit.normal.approx_eq(&Vec3::new(-1., 0., 0.)));
+    }
+
+    #[test]
+    fn miss() {
+        let r = Ray {
+            start: Vec3::new(-5., 0., 2.5),
+            dir: Vec3::new(1., 0., 0.),
+        };
+        assert!(cylinder(true).reflect(&r).is_none());
+        let r = Ray {
+            start: Vec3::new(0.5, 0., 5.),
+            dir: Vec3::new(0., 0., -1.),
+        };
+        assert!(cylinder(false).reflect(&r).is_none());
+    }
+}
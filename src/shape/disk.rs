@@ -0,0 +1,108 @@
+use std::f32::consts::PI;
+
+use crate::ray::Ray;
+use crate::shape::{components, intersect_plane, orthonormal_basis, Hit, Shape};
+use crate::vec3::Vec3;
+
+/// Two-sided disk
+/// Texture coordinates are the angle around the normal and the distance from the center
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Disk {
+    pub center: Vec3,
+    /// Unit normal
+    pub normal: Vec3,
+    pub radius: f32,
+}
+
+impl Shape for Disk {
+    fn reflect(&self, ray: &Ray) -> Option<Hit> {
+        let (_, p, normal) = intersect_plane(ray, &self.center, &self.normal)?;
+        let d = p - self.center;
+        let r = d.mag();
+        if r > self.radius {
+            return None;
+        }
+        let (t, b) = orthonormal_basis(&self.normal);
+        let phi = d.dot(&b).atan2(d.dot(&t)).rem_euclid(2. * PI);
+        let radial = t.mul_n(phi.cos()) + b.mul_n(phi.sin());
+        Some(Hit {
+            ray: Ray {
+                start: p,
+                dir: ray.dir.reflect(&normal).norm(),
+            },
+            normal,
+            uv: (phi / (2. * PI), r / self.radius),
+            dpdu: self.normal.cross(&radial).mul_n(2. * PI * r),
+            dpdv: radial.mul_n(self.radius),
+            ..Default::default()
+        })
+    }
+
+    fn center(&self) -> Vec3 {
+        self.center
+    }
+
+    fn area(&self) -> f32 {
+        PI * self.radius * self.radius
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.extend(components(&[self.center, self.normal]));
+        out.push(self.radius);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::math::approx_eq;
+    use crate::ray::Ray;
+    use crate::shape::disk::Disk;
+    use crate::shape::Shape;
+    use crate::vec3::Vec3;
+
+    fn disk() -> Disk {
+        Disk {
+            center: Vec3::new(0., 0., 0.),
+            normal: Vec3::new(0., 1., 0.),
+            radius: 2.,
+        }
+    }
+
+    #[test]
+    fn reflect() {
+        let r = Ray {
+            start: Vec3::new(1., 3., 0.),
+            dir: Vec3::new(0., -1., 0.),
+        };
+        let hit = disk().reflect(&r).unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(1., 0., 0.)));
+        assert!(hit.normal.approx_eq(&Vec3::new(0., 1., 0.)));
+        assert!(approx_eq(hit.uv.1, 0.5));
+        assert!(approx_eq(hit.dpdu.dot(&hit.dpdv), 0.));
+    }
+
+    #[test]
+    fn uv_angle() {
+        let hit = |x: f32, z: f32| {
+            let r = Ray {
+                start: Vec3::new(x, 1., z),
+                dir: Vec3::new(0., -1., 0.),
+            };
+            disk().reflect(&r).unwrap().uv.0
+        };
+        // the tangent basis of the y normal is x and -z
+        assert!(approx_eq(hit(1., 0.), 0.));
+        assert!(approx_eq(hit(0., -1.), 0.25));
+        assert!(approx_eq(hit(-1., 0.), 0.5));
+        assert!(approx_eq(hit(0., 1.), 0.75));
+    }
+
+    #[test]
+    fn miss_outside() {
+        let r = Ray {
+            start: Vec3::new(1.5, 1., 1.5),
+            dir: Vec3::new(0., -1., 0.),
+        };
+        assert!(disk().reflect(&r).is_none());
+    }
+}
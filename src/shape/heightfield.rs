@@ -0,0 +1,339 @@
+use std::io;
+
+use crate::image::Image;
+use crate::ray::Ray;
+use crate::shape::cuboid::Cuboid;
+use crate::shape::{components, intersect_triangle, Hit, Shape, HIT_EPSILON};
+use crate::vec3::Vec3;
+
+/// Terrain surface over the grid of heights in the xy plane, every grid cell is split into two
+/// triangles shaded with normals interpolated from the grid vertices
+/// Texture coordinates span the whole grid
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heightfield {
+    /// Heights in [0, 1] range, row by row
+    pub heights: Vec<f32>,
+    /// Grid vertex count along x
+    pub width: usize,
+    /// Grid vertex count along y
+    pub depth: usize,
+    /// Corner of the grid at zero height
+    pub min: Vec3,
+    /// Grid extent along x and y, and the height of 1
+    pub size: Vec3,
+    /// Vertex normals, row by row
+    normals: Vec<Vec3>,
+    /// Lowest and highest heights of every cell, row by row
+    bounds: Vec<(f32, f32)>,
+}
+
+impl Heightfield {
+    pub fn new(
+        heights: Vec<f32>,
+        width: usize,
+        depth: usize,
+        min: Vec3,
+        size: Vec3,
+    ) -> Heightfield {
+        assert!(
+            width >= 2 && depth >= 2,
+            "heightfield needs at least 2x2 vertices"
+        );
+        assert_eq!(heights.len(), width * depth);
+        let mut h = Heightfield {
+            heights,
+            width,
+            depth,
+            min,
+            size,
+            normals: vec![],
+            bounds: vec![],
+        };
+        h.normals = (0..width * depth)
+            .map(|i| h.vertex_normal(i % width, i / width))
+            .collect();
+        h.bounds = (0..(width - 1) * (depth - 1))
+            .map(|i| {
+                let (x, y) = (i % (width - 1), i / (width - 1));
+                let hs = [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(dx, dy)| h.height(x + dx, y + dy));
+                let min = hs.iter().copied().fold(f32::MAX, f32::min);
+                let max = hs.iter().copied().fold(f32::MIN, f32::max);
+                (min, max)
+            })
+            .collect();
+        h
+    }
+
+    /// Heights from the lightness of the grayscale image pixels, image rows go along y
+    /// Images narrower or shorter than 2 pixels hold no grid cell and are rejected
+    pub fn from_image(image: &Image, min: Vec3, size: Vec3) -> io::Result<Heightfield> {
+        if image.width < 2 || image.height < 2 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "heightfield image smaller than 2x2",
+            ));
+        }
+        let heights = image
+            .pixels
+            .iter()
+            .map(|c| c.lightness().clamp(0., 1.))
+            .collect();
+        Ok(Heightfield::new(
+            heights,
+            image.width,
+            image.height,
+            min,
+            size,
+        ))
+    }
+
+    /// Load heights from the PPM or PNG image
+    pub fn load(path: &str, min: Vec3, size: Vec3) -> io::Result<Heightfield> {
+        Heightfield::from_image(&Image::load(path)?, min, size)
+    }
+
+    fn height(&self, x: usize, y: usize) -> f32 {
+        self.heights[y * self.width + x]
+    }
+
+    fn cell_size(&self) -> (f32, f32) {
+        (
+            self.size.x / (self.width - 1) as f32,
+            self.size.y / (self.depth - 1) as f32,
+        )
+    }
+
+    fn vertex(&self, x: usize, y: usize) -> Vec3 {
+        let (dx, dy) = self.cell_size();
+        self.min
+            + Vec3::new(
+                x as f32 * dx,
+                y as f32 * dy,
+                self.height(x, y) * self.size.z,
+            )
+    }
+
+    /// Normal by central differences of neighbour heights
+    fn vertex_normal(&self, x: usize, y: usize) -> Vec3 {
+        let (dx, dy) = self.cell_size();
+        let slope =
+            |a: f32, b: f32, steps: usize, step: f32| (b - a) * self.size.z / (steps as f32 * step);
+        let (x0, x1) = (x.saturating_sub(1), (x + 1).min(self.width - 1));
+        let (y0, y1) = (y.saturating_sub(1), (y + 1).min(self.depth - 1));
+        let sx = slope(self.height(x0, y), self.height(x1, y), x1 - x0, dx);
+        let sy = slope(self.height(x, y0), self.height(x, y1), y1 - y0, dy);
+        Vec3::new(-sx, -sy, 1.).norm()
+    }
+
+    /// Closest hit of the ray with the two triangles of the cell, within the ray parameters
+    fn cell_hit(&self, ray: &Ray, x: usize, y: usize, t_range: (f32, f32)) -> Option<Hit> {
+        let (h_min, h_max) = self.bounds[y * (self.width - 1) + x];
+        let z = |t: f32| (ray.with_param(t).z - self.min.z) / self.size.z;
+        let (z0, z1) = (z(t_range.0), z(t_range.1));
+        // ray passes above or below the cell
+        if z0.min(z1) > h_max + 1e-6 || z0.max(z1) < h_min - 1e-6 {
+            return None;
+        }
+        let corners = [(x, y), (x + 1, y), (x + 1, y + 1), (x, y + 1)];
+        [[0, 1, 2], [0, 2, 3]]
+            .iter()
+            .filter_map(|tri| {
+                let [a, b, c] = tri.map(|i| corners[i]);
+                let [pa, pb, pc] = [a, b, c].map(|(x, y)| self.vertex(x, y));
+                let (t, u, v) = intersect_triangle(ray, &pa, &pb, &pc)?;
+                (t >= HIT_EPSILON).then_some((t, [a, b, c], [pa, pb, pc], (u, v)))
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(t, vs, ps, (u, v))| {
+                let [na, nb, nc] = vs.map(|(x, y)| self.normals[y * self.width + x]);
+                let smooth = (na.mul_n(1. - u - v) + nb.mul_n(u) + nc.mul_n(v)).norm();
+                let mut geometric = (ps[1] - ps[0]).cross(&(ps[2] - ps[0])).norm();
+                if geometric.dot(&ray.dir) > 0. {
+                    geometric = -geometric;
+                }
+                let smooth = if smooth.dot(&geometric) < 0. {
+                    -smooth
+                } else {
+                    smooth
+                };
+                let p = ray.with_param(t);
+                let rel = p - self.min;
+                let hit = Hit {
+                    ray: Ray {
+                        start: p,
+                        dir: ray.dir.reflect(&geometric).norm(),
+                    },
+                    normal: geometric,
+                    uv: (
+                        (rel.x / self.size.x).clamp(0., 1.),
+                        (rel.y / self.size.y).clamp(0., 1.),
+                    ),
+                    dpdu: Vec3::new(self.size.x, 0., -smooth.x / smooth.z * self.size.x),
+                    dpdv: Vec3::new(0., self.size.y, -smooth.y / smooth.z * self.size.y),
+                    ..Default::default()
+                };
+                hit.shade(&smooth)
+            })
+    }
+}
+
+impl Shape for Heightfield {
+    /// Walks the grid cells along the ray with 2D DDA
+    /// [ref](http://www.cse.yorku.ca/~amana/research/grid.pdf)
+    fn reflect(&self, ray: &Ray) -> Option<Hit> {
+        let bounds = Cuboid {
+            min: self.min,
+            max: self.min + self.size,
+        };
+        let ((t_enter, _), (t_exit, _)) = bounds.slabs(ray)?;
+        let mut t = t_enter.max(0.);
+        let (dx, dy) = self.cell_size();
+        let p = ray.with_param(t) - self.min;
+        let (cells_x, cells_y) = (self.width - 1, self.depth - 1);
+        let cell = |v: f32, d: f32, n: usize| ((v / d).floor().max(0.) as usize).min(n - 1);
+        let (mut x, mut y) = (cell(p.x, dx, cells_x), cell(p.y, dy, cells_y));
+        // ray parameter of crossing the next cell boundary along the axis and the step between
+        let axis = |start: f32, dir: f32, cell: usize, d: f32| {
+            if dir == 0. {
+                return (f32::MAX, f32::MAX);
+            }
+            let next = if dir > 0. { cell + 1 } else { cell } as f32 * d;
+            (t + (next - start) / dir, d / dir.abs())
+        };
+        let (mut next_x, step_x) = axis(p.x, ray.dir.x, x, dx);
+        let (mut next_y, step_y) = axis(p.y, ray.dir.y, y, dy);
+        loop {
+            let cell_exit = next_x.min(next_y).min(t_exit);
+            if let Some(hit) = self.cell_hit(ray, x, y, (t, cell_exit)) {
+                return Some(hit);
+            }
+            if cell_exit >= t_exit {
+                return None;
+            }
+            t = cell_exit;
+            if next_x < next_y {
+                if (ray.dir.x > 0. && x + 1 >= cells_x) || (ray.dir.x < 0. && x == 0) {
+                    return None;
+                }
+                x = if ray.dir.x > 0. { x + 1 } else { x - 1 };
+                next_x += step_x;
+            } else {
+                if (ray.dir.y > 0. && y + 1 >= cells_y) || (ray.dir.y < 0. && y == 0) {
+                    return None;
+                }
+                y = if ray.dir.y > 0. { y + 1 } else { y - 1 };
+                next_y += step_y;
+            }
+        }
+    }
+
+    fn center(&self) -> Vec3 {
+        self.min + self.size.mul_n(0.5)
+    }
+
+    fn area(&self) -> f32 {
+        let mut area = 0.;
+        for y in 0..self.depth - 1 {
+            for x in 0..self.width - 1 {
+                let [a, b, c, d] = [(x, y), (x + 1, y), (x + 1, y + 1), (x, y + 1)]
+                    .map(|(x, y)| self.vertex(x, y));
+                area += ((b - a).cross(&(c - a)).mag() + (c - a).cross(&(d - a)).mag()) / 2.;
+            }
+        }
+        area
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.extend([self.width as f32, self.depth as f32]);
+        out.extend(components(&[self.min, self.size]));
+        out.extend(&self.heights);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env::temp_dir;
+    use std::fs::remove_file;
+
+    use crate::color::Color;
+    use crate::image::Image;
+    use crate::math::approx_eq;
+    use crate::ray::Ray;
+    use crate::shape::heightfield::Heightfield;
+    use crate::shape::Shape;
+    use crate::vec3::Vec3;
+
+    /// Ridge along y at x of 2, rising to the height of 2
+    fn ridge() -> Heightfield {
+        let heights = (0..25).map(|i| [0., 0.5, 1., 0.5, 0.][i % 5]).collect();
+        Heightfield::new(heights, 5, 5, Vec3::zero(), Vec3::new(4., 4., 2.))
+    }
+
+    fn down(x: f32, y: f32) -> Ray {
+        Ray {
+            start: Vec3::new(x, y, 10.),
+            dir: Vec3::new(0., 0., -1.),
+        }
+    }
+
+    #[test]
+    fn reflect_from_above() {
+        let h = ridge();
+        let hit = h.reflect(&down(2., 2.5)).unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(2., 2.5, 2.)));
+        // smooth normal at the ridge top points up
+        assert!(hit.normal.approx_eq(&Vec3::new(0., 0., 1.)));
+        let hit = h.reflect(&down(0.5, 1.)).unwrap();
+        assert!(approx_eq(hit.ray.start.z, 0.5));
+        assert!(hit.normal.x < 0. && hit.normal.z > 0.);
+        assert!(approx_eq(hit.uv.0, 0.125) && approx_eq(hit.uv.1, 0.25));
+    }
+
+    #[test]
+    fn reflect_side() {
+        let h = ridge();
+        // horizontal ray at the height of 1 hits the slope halfway up
+        let r = Ray {
+            start: Vec3::new(-3., 1.3, 1.),
+            dir: Vec3::new(1., 0., 0.),
+        };
+        let hit = h.reflect(&r).unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(1., 1.3, 1.)), "{hit:?}");
+        assert!(hit.ray.dir.z > 0.);
+        // the same ray from the other side
+        let r = Ray {
+            start: Vec3::new(7., 1.3, 1.),
+            dir: Vec3::new(-1., 0., 0.),
+        };
+        let hit = h.reflect(&r).unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(3., 1.3, 1.)), "{hit:?}");
+    }
+
+    #[test]
+    fn miss() {
+        let h = ridge();
+        let r = Ray {
+            start: Vec3::new(-3., 1., 2.5),
+            dir: Vec3::new(1., 0.2, 0.).norm(),
+        };
+        assert!(h.reflect(&r).is_none());
+        assert!(h.reflect(&down(5., 1.)).is_none());
+    }
+
+    #[test]
+    fn load() {
+        let mut image = Image::new(3, 2);
+        image.set(1, 1, Color::WHITE);
+        let path = temp_dir().join(format!("sunny-{}-heightfield.ppm", std::process::id()));
+        let path = path.to_str().unwrap();
+        image.save_ppm(path).unwrap();
+        let h = Heightfield::load(path, Vec3::zero(), Vec3::new(2., 1., 1.)).unwrap();
+        remove_file(path).unwrap();
+        assert_eq!((h.width, h.depth), (3, 2));
+        let hit = h.reflect(&down(1., 1.)).unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(1., 1., 1.)));
+        assert!(h.area() > 2.);
+        let line = Image::new(4, 1);
+        assert!(Heightfield::from_image(&line, Vec3::zero(), Vec3::new(1., 1., 1.)).is_err());
+    }
+}
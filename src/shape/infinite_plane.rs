@@ -0,0 +1,103 @@
+use crate::ray::Ray;
+use crate::shape::{components, intersect_plane, orthonormal_basis, Hit, Shape};
+use crate::vec3::Vec3;
+
+/// Two-sided plane through the `point`
+/// Texture coordinates repeat every unit along the plane axes
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct InfinitePlane {
+    pub point: Vec3,
+    /// Unit normal
+    pub normal: Vec3,
+}
+
+impl Shape for InfinitePlane {
+    fn reflect(&self, ray: &Ray) -> Option<Hit> {
+        let (_, p, normal) = intersect_plane(ray, &self.point, &self.normal)?;
+        let (u, v) = orthonormal_basis(&self.normal);
+        let d = p - self.point;
+        Some(Hit {
+            ray: Ray {
+                start: p,
+                dir: ray.dir.reflect(&normal).norm(),
+            },
+            normal,
+            uv: (d.dot(&u).rem_euclid(1.), d.dot(&v).rem_euclid(1.)),
+            dpdu: u,
+            dpdv: v,
+            ..Default::default()
+        })
+    }
+
+    fn center(&self) -> Vec3 {
+        self.point
+    }
+
+    fn area(&self) -> f32 {
+        f32::INFINITY
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.extend(components(&[self.point, self.normal]));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ray::Ray;
+    use crate::shape::infinite_plane::InfinitePlane;
+    use crate::shape::Shape;
+    use crate::vec3::Vec3;
+
+    fn plane() -> InfinitePlane {
+        InfinitePlane {
+            point: Vec3::new(0., 0., -1.),
+            normal: Vec3::new(0., 0., 1.),
+        }
+    }
+
+    #[test]
+    fn reflect() {
+        let r = Ray {
+            start: Vec3::new(0., 0., 1.),
+            dir: Vec3::new(1., 0., -1.).norm(),
+        };
+        let hit = plane().reflect(&r).unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(2., 0., -1.)));
+        assert!(hit.ray.dir.approx_eq(&Vec3::new(1., 0., 1.).norm()));
+        assert!(hit.normal.approx_eq(&Vec3::new(0., 0., 1.)));
+        assert!((0. ..1.).contains(&hit.uv.0) && (0. ..1.).contains(&hit.uv.1));
+    }
+
+    #[test]
+    fn reflect_below() {
+        let r = Ray {
+            start: Vec3::new(0., 0., -3.),
+            dir: Vec3::new(0., 0., 1.),
+        };
+        let hit = plane().reflect(&r).unwrap();
+        assert!(hit.normal.approx_eq(&Vec3::new(0., 0., -1.)));
+        assert!(hit.ray.dir.approx_eq(&Vec3::new(0., 0., -1.)));
+    }
+
+    #[test]
+    fn miss() {
+        let parallel = Ray {
+            start: Vec3::new(0., 0., 1.),
+            dir: Vec3::new(1., 0., 0.),
+        };
+        assert!(plane().reflect(&parallel).is_none());
+        let away = Ray {
+            start: Vec3::new(0., 0., 1.),
+            dir: Vec3::new(0., 0., 1.),
+        };
+        assert!(plane().reflect(&away).is_none());
+        // reflected ray doesn't hit the plane again
+        let on = Ray {
+            start: Vec3::new(0., 0., -1.),
+            dir: Vec3::new(1., 0., 1.).norm(),
+        };
+        assert!(plane().reflect(&on).is_none());
+        assert_eq!(plane().area(), f32::INFINITY);
+    }
+}
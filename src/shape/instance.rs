@@ -0,0 +1,148 @@
+use std::sync::Arc;
+
+use crate::ray::Ray;
+use crate::shape::{Crossing, Hit, Shape};
+use crate::transform::Transform;
+use crate::vec3::Vec3;
+
+/// Shape placed into the world by the transform, rays are intersected with it in its object
+/// space
+/// Shape is shared, so that the same geometry can be instanced many times
+#[derive(Debug, Clone)]
+pub struct Instance {
+    pub shape: Arc<dyn Shape>,
+    /// Object to world transform
+    pub transform: Transform,
+}
+
+impl Shape for Instance {
+    fn reflect(&self, ray: &Ray) -> Option<Hit> {
+        let local = self.transform.inverse().ray(ray);
+        let hit = self.shape.reflect(&Ray {
+            start: local.start,
+            dir: local.dir.norm(),
+        })?;
+        let mut normal = self.transform.normal(&hit.normal).norm();
+        if normal.dot(&ray.dir) > 0. {
+            normal = -normal;
+        }
+        Some(Hit {
+            ray: Ray {
+                start: self.transform.point(&hit.ray.start),
+                // reflection is not preserved by non-uniform scale
+                dir: ray.dir.reflect(&normal).norm(),
+            },
+            normal,
+            dpdu: self.transform.vector(&hit.dpdu),
+            dpdv: self.transform.vector(&hit.dpdv),
+            ..hit
+        })
+    }
+
+    fn center(&self) -> Vec3 {
+        self.transform.point(&self.shape.center())
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<(Crossing, Crossing)> {
+        let local = self.transform.inverse().ray(ray);
+        let len = local.dir.mag();
+        let local = Ray {
+            start: local.start,
+            dir: local.dir.mul_n(1. / len),
+        };
+        // local ray parameters are scaled by the normalization of the direction
+        let crossing = |c: &Crossing| Crossing {
+            t: c.t / len,
+            normal: self.transform.normal(&c.normal),
+            uv: c.uv,
+            dpdu: self.transform.vector(&c.dpdu),
+            dpdv: self.transform.vector(&c.dpdv),
+        };
+        self.shape
+            .spans(&local)
+            .iter()
+            .map(|(a, b)| (crossing(a), crossing(b)))
+            .collect()
+    }
+
+    /// Exact for uniform scale, approximated by the average scale otherwise
+    fn area(&self) -> f32 {
+        self.shape.area() * self.transform.matrix.det3().abs().powf(2. / 3.)
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        self.shape.fingerprint(out);
+        out.extend(self.transform.matrix.m.iter().flatten());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f32::consts::PI;
+    use std::sync::Arc;
+
+    use crate::math::approx_eq;
+    use crate::ray::Ray;
+    use crate::shape::cuboid::Cuboid;
+    use crate::shape::instance::Instance;
+    use crate::shape::sphere::Sphere;
+    use crate::shape::Shape;
+    use crate::transform::Transform;
+    use crate::vec3::Vec3;
+
+    #[test]
+    fn reflect_scaled() {
+        let sphere = Arc::new(Sphere {
+            center: Vec3::zero(),
+            radius: 1.,
+        });
+        let ellipsoid = Instance {
+            shape: sphere,
+            transform: Transform::scale(Vec3::new(2., 1., 1.))
+                .then(&Transform::translate(Vec3::new(0., 0., 5.))),
+        };
+        let r = Ray {
+            start: Vec3::new(-10., 0., 5.),
+            dir: Vec3::new(1., 0., 0.),
+        };
+        let hit = ellipsoid.reflect(&r).unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(-2., 0., 5.)));
+        assert!(hit.normal.approx_eq(&Vec3::new(-1., 0., 0.)));
+        assert!(hit.ray.dir.approx_eq(&Vec3::new(-1., 0., 0.)));
+        // off-axis normal is skewed by the scale
+        let r = Ray {
+            start: Vec3::new(2f32.sqrt(), 10., 5.),
+            dir: Vec3::new(0., -1., 0.),
+        };
+        let hit = ellipsoid.reflect(&r).unwrap();
+        assert!(hit
+            .ray
+            .start
+            .approx_eq(&Vec3::new(2f32.sqrt(), 0.5f32.sqrt(), 5.)));
+        assert!(approx_eq(hit.normal.x / hit.normal.y, 0.5));
+        assert!(ellipsoid.center().approx_eq(&Vec3::new(0., 0., 5.)));
+    }
+
+    #[test]
+    fn shared() {
+        let cube: Arc<dyn Shape> = Arc::new(Cuboid::new(Vec3::zero(), Vec3::diag(1.)));
+        let instances: Vec<Instance> = (0..10)
+            .map(|i| Instance {
+                shape: cube.clone(),
+                transform: Transform::rotate_z(PI / 4.).then(&Transform::translate(Vec3::new(
+                    i as f32 * 3.,
+                    0.,
+                    0.,
+                ))),
+            })
+            .collect();
+        assert_eq!(Arc::strong_count(&cube), 11);
+        let r = Ray {
+            start: Vec3::new(27., -5., 0.),
+            dir: Vec3::new(0., 1., 0.),
+        };
+        let hit = instances[9].reflect(&r).unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(27., -0.5f32.sqrt(), 0.)));
+        assert!(approx_eq(instances[9].area(), 6.));
+    }
+}
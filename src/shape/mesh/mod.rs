@@ -0,0 +1,380 @@
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+use crate::color::Color;
+use crate::ray::Ray;
+use crate::shape::cuboid::Cuboid;
+use crate::shape::{components, intersect_triangle, Hit, Shape, HIT_EPSILON};
+use crate::vec3::Vec3;
+
+pub mod ply;
+pub mod stl;
+
+/// Largest triangle count of the BVH leaf
+const LEAF_SIZE: usize = 4;
+
+/// Capacity of the traversal stack, one more than the deepest possible tree
+const STACK_SIZE: usize = usize::BITS as usize + 1;
+
+/// Triangle mesh, accelerated by the bounding volume hierarchy built on construction
+/// Vertex normals make the surface shaded smooth, vertex colors are reported by hits
+/// Without vertex texture coordinates, every triangle spans its own uv space by barycentric
+/// coordinates
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mesh {
+    pub positions: Vec<Vec3>,
+    /// Vertex indices of triangles, counter-clockwise
+    pub triangles: Vec<[usize; 3]>,
+    pub normals: Option<Vec<Vec3>>,
+    pub colors: Option<Vec<Color>>,
+    pub uvs: Option<Vec<(f32, f32)>>,
+    nodes: Vec<Node>,
+    /// Triangle indices, ordered by BVH leaves
+    order: Vec<usize>,
+}
+
+/// BVH node, the first child of the inner node follows it
+#[derive(Debug, Clone, PartialEq)]
+struct Node {
+    bounds: Cuboid,
+    /// First triangle in the order of the leaf, or the second child of the inner node
+    index: usize,
+    /// Triangle count of the leaf, 0 for inner nodes
+    count: usize,
+}
+
+impl Mesh {
+    pub fn new(positions: Vec<Vec3>, triangles: Vec<[usize; 3]>) -> Mesh {
+        assert!(
+            triangles.iter().flatten().all(|i| *i < positions.len()),
+            "mesh triangle refers to missing vertex"
+        );
+        let mut mesh = Mesh {
+            positions,
+            triangles,
+            normals: None,
+            colors: None,
+            uvs: None,
+            nodes: vec![],
+            order: vec![],
+        };
+        mesh.order = (0..mesh.triangles.len()).collect();
+        if !mesh.triangles.is_empty() {
+            mesh.build(0, mesh.triangles.len());
+        }
+        mesh
+    }
+
+    pub fn with_normals(self, normals: Vec<Vec3>) -> Mesh {
+        assert_eq!(normals.len(), self.positions.len());
+        Mesh {
+            normals: Some(normals),
+            ..self
+        }
+    }
+
+    pub fn with_colors(self, colors: Vec<Color>) -> Mesh {
+        assert_eq!(colors.len(), self.positions.len());
+        Mesh {
+            colors: Some(colors),
+            ..self
+        }
+    }
+
+    pub fn with_uvs(self, uvs: Vec<(f32, f32)>) -> Mesh {
+        assert_eq!(uvs.len(), self.positions.len());
+        Mesh {
+            uvs: Some(uvs),
+            ..self
+        }
+    }
+
+    /// Load PLY or STL mesh, depending on the file extension
+    pub fn load(path: &str) -> std::io::Result<Mesh> {
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("ply") => ply::load_ply(path),
+            Some("stl") => stl::load_stl(path),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("unsupported mesh format of {path}"),
+            )),
+        }
+    }
+
+    fn vertices(&self, triangle: usize) -> [Vec3; 3] {
+        self.triangles[triangle].map(|i| self.positions[i])
+    }
+
+    fn bounds(&self, triangles: &[usize]) -> Cuboid {
+        let mut b = Cuboid {
+            min: Vec3::diag(f32::MAX),
+            max: Vec3::diag(f32::MIN),
+        };
+        for v in triangles.iter().flat_map(|t| self.vertices(*t)) {
+            b.min = Vec3::new(b.min.x.min(v.x), b.min.y.min(v.y), b.min.z.min(v.z));
+            b.max = Vec3::new(b.max.x.max(v.x), b.max.y.max(v.y), b.max.z.max(v.z));
+        }
+        b
+    }
+
+    /// Builds the node of triangles in the order range, splitting them by centroids at the
+    /// median of the longest axis
+    fn build(&mut self, start: usize, end: usize) {
+        let bounds = self.bounds(&self.order[start..end]);
+        let node = self.nodes.len();
+        self.nodes.push(Node {
+            bounds,
+            index: start,
+            count: end - start,
+        });
+        if end - start <= LEAF_SIZE {
+            return;
+        }
+        let size = bounds.max - bounds.min;
+        let mut order = std::mem::take(&mut self.order);
+        let centroid = |t: usize| {
+            let [a, b, c] = self.vertices(t);
+            let p = a + b + c;
+            if size.x >= size.y && size.x >= size.z {
+                p.x
+            } else if size.y >= size.z {
+                p.y
+            } else {
+                p.z
+            }
+        };
+        let mid = (start + end) / 2;
+        order[start..end]
+            .select_nth_unstable_by(mid - start, |a, b| centroid(*a).total_cmp(&centroid(*b)));
+        self.order = order;
+        self.build(start, mid);
+        self.nodes[node].index = self.nodes.len();
+        self.nodes[node].count = 0;
+        self.build(mid, end);
+    }
+
+    fn hit(&self, ray: &Ray, t: f32, triangle: usize, u: f32, v: f32) -> Hit {
+        let ids = self.triangles[triangle];
+        let [a, b, c] = self.vertices(triangle);
+        let w = [1. - u - v, u, v];
+        let (e1, e2) = (b - a, c - a);
+        let mut normal = e1.cross(&e2).norm();
+        if normal.dot(&ray.dir) > 0. {
+            normal = -normal;
+        }
+        let (uv, dpdu, dpdv) = match &self.uvs {
+            Some(uvs) => {
+                let [ta, tb, tc] = ids.map(|i| uvs[i]);
+                let uv = (
+                    w[0] * ta.0 + w[1] * tb.0 + w[2] * tc.0,
+                    w[0] * ta.1 + w[1] * tb.1 + w[2] * tc.1,
+                );
+                let (du1, dv1) = (tb.0 - ta.0, tb.1 - ta.1);
+                let (du2, dv2) = (tc.0 - ta.0, tc.1 - ta.1);
+                let det = du1 * dv2 - dv1 * du2;
+                if det.abs() < 1e-12 {
+                    (uv, e1, e2)
+                } else {
+                    (
+                        uv,
+                        (e1.mul_n(dv2) - e2.mul_n(dv1)).mul_n(1. / det),
+                        (e2.mul_n(du1) - e1.mul_n(du2)).mul_n(1. / det),
+                    )
+                }
+            }
+            None => ((u, v), e1, e2),
+        };
+        let color = self.colors.as_ref().map(|colors| {
+            let [ca, cb, cc] = ids.map(|i| colors[i]);
+            Color::rgb(
+                w[0] * ca.r + w[1] * cb.r + w[2] * cc.r,
+                w[0] * ca.g + w[1] * cb.g + w[2] * cc.g,
+                w[0] * ca.b + w[1] * cb.b + w[2] * cc.b,
+            )
+        });
+        let p = ray.with_param(t);
+        let hit = Hit {
+            ray: Ray {
+                start: p,
+                dir: ray.dir.reflect(&normal).norm(),
+            },
+            normal,
+            uv,
+            dpdu,
+            dpdv,
+            color,
+            ..Default::default()
+        };
+        let Some(normals) = &self.normals else {
+            return hit;
+        };
+        let [na, nb, nc] = ids.map(|i| normals[i]);
+        let smooth = (na.mul_n(w[0]) + nb.mul_n(w[1]) + nc.mul_n(w[2])).norm();
+        if smooth.dot(&normal) < 0. {
+            hit.shade(&-smooth)
+        } else {
+            hit.shade(&smooth)
+        }
+    }
+}
+
+impl Shape for Mesh {
+    fn reflect(&self, ray: &Ray) -> Option<Hit> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+        // ray parameter, triangle and barycentric coordinates of the closest hit
+        let mut closest: Option<(f32, usize, f32, f32)> = None;
+        // median splits keep the tree depth below the bits of the triangle count
+        let (mut stack, mut len) = ([0; STACK_SIZE], 1);
+        while len > 0 {
+            len -= 1;
+            let i = stack[len];
+            let node = &self.nodes[i];
+            let Some(((near, _), _)) = node.bounds.slabs(ray) else {
+                continue;
+            };
+            if closest.is_some_and(|c| near > c.0) {
+                continue;
+            }
+            if node.count == 0 {
+                stack[len] = node.index;
+                stack[len + 1] = i + 1;
+                len += 2;
+                continue;
+            }
+            for &triangle in &self.order[node.index..node.index + node.count] {
+                let [a, b, c] = self.vertices(triangle);
+                let Some((t, u, v)) = intersect_triangle(ray, &a, &b, &c) else {
+                    continue;
+                };
+                if t >= HIT_EPSILON && closest.is_none_or(|c| t < c.0) {
+                    closest = Some((t, triangle, u, v));
+                }
+            }
+        }
+        closest.map(|(t, triangle, u, v)| self.hit(ray, t, triangle, u, v))
+    }
+
+    fn center(&self) -> Vec3 {
+        self.nodes
+            .first()
+            .map_or(Vec3::zero(), |n| n.bounds.center())
+    }
+
+    fn area(&self) -> f32 {
+        (0..self.triangles.len())
+            .map(|t| {
+                let [a, b, c] = self.vertices(t);
+                (b - a).cross(&(c - a)).mag() / 2.
+            })
+            .sum()
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.extend(components(&self.positions));
+        out.extend(self.triangles.iter().flatten().map(|i| *i as f32));
+        out.extend(components(self.normals.as_deref().unwrap_or_default()));
+        let colors = self.colors.as_deref().unwrap_or_default();
+        out.extend(colors.iter().flat_map(|c| [c.r, c.g, c.b]));
+        let uvs = self.uvs.as_deref().unwrap_or_default();
+        out.extend(uvs.iter().flat_map(|(u, v)| [*u, *v]));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::color::Color;
+    use crate::math::approx_eq;
+    use crate::ray::Ray;
+    use crate::shape::mesh::Mesh;
+    use crate::shape::Shape;
+    use crate::vec3::Vec3;
+
+    /// Grid of n by n unit squares in the xy plane, split into triangles
+    fn grid(n: usize) -> Mesh {
+        let positions = (0..(n + 1) * (n + 1))
+            .map(|i| Vec3::new((i % (n + 1)) as f32, (i / (n + 1)) as f32, 0.))
+            .collect();
+        let triangles = (0..n * n)
+            .flat_map(|i| {
+                let a = i / n * (n + 1) + i % n;
+                let (b, c, d) = (a + 1, a + n + 2, a + n + 1);
+                [[a, b, c], [a, c, d]]
+            })
+            .collect();
+        Mesh::new(positions, triangles)
+    }
+
+    fn down(x: f32, y: f32) -> Ray {
+        Ray {
+            start: Vec3::new(x, y, 5.),
+            dir: Vec3::new(0., 0., -1.),
+        }
+    }
+
+    #[test]
+    fn reflect() {
+        let m = grid(8);
+        assert!(m.nodes.len() > 1);
+        for (x, y) in [(0.3, 0.2), (7.9, 7.5), (4.5, 3.25), (2.1, 6.9)] {
+            let hit = m.reflect(&down(x, y)).unwrap();
+            assert!(hit.ray.start.approx_eq(&Vec3::new(x, y, 0.)), "{hit:?}");
+            assert!(hit.normal.approx_eq(&Vec3::new(0., 0., 1.)));
+            assert!(hit.ray.dir.approx_eq(&Vec3::new(0., 0., 1.)));
+            assert_eq!(hit.color, None);
+        }
+        assert!(m.reflect(&down(8.5, 3.)).is_none());
+        let below = Ray {
+            start: Vec3::new(3., 3., -1.),
+            dir: Vec3::new(0., 0., -1.),
+        };
+        assert!(m.reflect(&below).is_none());
+        assert!(approx_eq(m.area(), 64.));
+        assert!(m.center().approx_eq(&Vec3::new(4., 4., 0.)));
+    }
+
+    #[test]
+    fn closest_of_layers() {
+        let mut positions = grid(2).positions;
+        positions.extend(grid(2).positions.iter().map(|p| p.with_z(1.)));
+        let mut triangles = grid(2).triangles;
+        triangles.extend(grid(2).triangles.iter().map(|t| t.map(|i| i + 9)));
+        let m = Mesh::new(positions, triangles);
+        let hit = m.reflect(&down(1.5, 0.5)).unwrap();
+        assert!(approx_eq(hit.ray.start.z, 1.));
+        let up = Ray {
+            start: Vec3::new(1.5, 0.5, -3.),
+            dir: Vec3::new(0., 0., 1.),
+        };
+        let hit = m.reflect(&up).unwrap();
+        assert!(approx_eq(hit.ray.start.z, 0.));
+        assert!(hit.normal.approx_eq(&Vec3::new(0., 0., -1.)));
+    }
+
+    #[test]
+    fn vertex_attributes() {
+        let m = Mesh::new(
+            vec![
+                Vec3::new(0., 0., 0.),
+                Vec3::new(2., 0., 0.),
+                Vec3::new(0., 2., 0.),
+            ],
+            vec![[0, 1, 2]],
+        )
+        .with_colors(vec![Color::RED, Color::GREEN, Color::BLUE])
+        .with_uvs(vec![(0., 0.), (1., 0.), (0., 1.)])
+        .with_normals(vec![
+            Vec3::new(0., 0., 1.),
+            Vec3::new(1., 0., 1.).norm(),
+            Vec3::new(0., 0., 1.),
+        ]);
+        let hit = m.reflect(&down(1., 0.5)).unwrap();
+        let color = hit.color.unwrap();
+        assert!(approx_eq(color.r, 0.25) && approx_eq(color.g, 0.5) && approx_eq(color.b, 0.25));
+        assert!(approx_eq(hit.uv.0, 0.5) && approx_eq(hit.uv.1, 0.25));
+        assert!(hit.dpdu.approx_eq(&Vec3::new(2., 0., 0.)));
+        // shading normal leans towards the tilted vertex
+        assert!(hit.normal.x > 0. && hit.normal.z > 0.);
+    }
+}
@@ -0,0 +1,353 @@
+use std::fs::read;
+use std::io::{Error, ErrorKind};
+
+use crate::color::Color;
+use crate::shape::mesh::Mesh;
+use crate::vec3::Vec3;
+
+/// Scalar type of the PLY property
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> std::io::Result<Scalar> {
+        Ok(match name {
+            "char" | "int8" => Scalar::I8,
+            "uchar" | "uint8" => Scalar::U8,
+            "short" | "int16" => Scalar::I16,
+            "ushort" | "uint16" => Scalar::U16,
+            "int" | "int32" => Scalar::I32,
+            "uint" | "uint32" => Scalar::U32,
+            "float" | "float32" => Scalar::F32,
+            "double" | "float64" => Scalar::F64,
+            _ => return Err(invalid(&format!("unknown PLY type {name}"))),
+        })
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+
+    /// Largest value of integer types, colors of them are scaled to [0, 1] by it
+    fn max(&self) -> f64 {
+        match self {
+            Scalar::I8 => i8::MAX as f64,
+            Scalar::U8 => u8::MAX as f64,
+            Scalar::I16 => i16::MAX as f64,
+            Scalar::U16 => u16::MAX as f64,
+            Scalar::I32 => i32::MAX as f64,
+            Scalar::U32 => u32::MAX as f64,
+            Scalar::F32 | Scalar::F64 => 1.,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Property {
+    name: String,
+    scalar: Scalar,
+    /// Type of the item count of list properties
+    list: Option<Scalar>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// Property values, read from text tokens or little endian binary data
+enum Values<'a> {
+    Ascii(std::str::SplitAsciiWhitespace<'a>),
+    Binary(&'a [u8]),
+}
+
+impl Values<'_> {
+    fn next(&mut self, scalar: Scalar) -> std::io::Result<f64> {
+        let end = || invalid("unexpected end of PLY file");
+        match self {
+            Values::Ascii(tokens) => tokens
+                .next()
+                .ok_or_else(end)?
+                .parse()
+                .map_err(|_| invalid("invalid PLY value")),
+            Values::Binary(data) => {
+                let size = scalar.size();
+                if data.len() < size {
+                    return Err(end());
+                }
+                let (b, rest) = data.split_at(size);
+                *data = rest;
+                Ok(match scalar {
+                    Scalar::I8 => b[0] as i8 as f64,
+                    Scalar::U8 => b[0] as f64,
+                    Scalar::I16 => i16::from_le_bytes([b[0], b[1]]) as f64,
+                    Scalar::U16 => u16::from_le_bytes([b[0], b[1]]) as f64,
+                    Scalar::I32 => i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    Scalar::U32 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    Scalar::F32 => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    Scalar::F64 => f64::from_le_bytes(b.try_into().unwrap()),
+                })
+            }
+        }
+    }
+}
+
+/// Load ASCII or binary little endian [PLY](https://paulbourke.net/dataformats/ply/) mesh
+/// Vertex normals (nx, ny, nz), colors (red, green, blue) and texture coordinates (u, v or s, t)
+/// are imported when present, polygon faces are split into triangle fans
+pub fn load_ply(path: &str) -> std::io::Result<Mesh> {
+    parse_ply(&read(path)?)
+}
+
+pub fn parse_ply(data: &[u8]) -> std::io::Result<Mesh> {
+    let header_end = data
+        .windows(11)
+        .position(|w| w == b"end_header\n")
+        .map(|i| i + 11)
+        .or_else(|| {
+            data.windows(12)
+                .position(|w| w == b"end_header\r\n")
+                .map(|i| i + 12)
+        })
+        .ok_or_else(|| invalid("no PLY header"))?;
+    let header =
+        std::str::from_utf8(&data[..header_end]).map_err(|_| invalid("invalid PLY header"))?;
+    let mut lines = header.lines();
+    if lines.next().map(str::trim) != Some("ply") {
+        return Err(invalid("not a PLY file"));
+    }
+    let mut binary = None;
+    let mut elements: Vec<Element> = vec![];
+    for line in lines {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["format", "ascii", _] => binary = Some(false),
+            ["format", "binary_little_endian", _] => binary = Some(true),
+            ["format", format, _] => {
+                return Err(invalid(&format!("unsupported PLY format {format}")))
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| invalid("invalid PLY element count"))?,
+                properties: vec![],
+            }),
+            ["property", "list", count, scalar, name] => {
+                let element = elements
+                    .last_mut()
+                    .ok_or_else(|| invalid("PLY property without element"))?;
+                element.properties.push(Property {
+                    name: name.to_string(),
+                    scalar: Scalar::parse(scalar)?,
+                    list: Some(Scalar::parse(count)?),
+                });
+            }
+            ["property", scalar, name] => {
+                let element = elements
+                    .last_mut()
+                    .ok_or_else(|| invalid("PLY property without element"))?;
+                element.properties.push(Property {
+                    name: name.to_string(),
+                    scalar: Scalar::parse(scalar)?,
+                    list: None,
+                });
+            }
+            _ => {}
+        }
+    }
+    let body = &data[header_end..];
+    let mut values = match binary.ok_or_else(|| invalid("no PLY format"))? {
+        true => Values::Binary(body),
+        false => Values::Ascii(
+            std::str::from_utf8(body)
+                .map_err(|_| invalid("invalid PLY data"))?
+                .split_ascii_whitespace(),
+        ),
+    };
+
+    let (mut positions, mut normals, mut colors, mut uvs) = (vec![], vec![], vec![], vec![]);
+    let mut triangles = vec![];
+    for element in &elements {
+        let find = |names: &[&str]| {
+            element
+                .properties
+                .iter()
+                .position(|p| names.contains(&p.name.as_str()))
+        };
+        let axes = |names: [&str; 3]| names.map(|n| find(&[n]));
+        let position = axes(["x", "y", "z"]);
+        let normal = axes(["nx", "ny", "nz"]);
+        let color = axes(["red", "green", "blue"]);
+        let uv = [
+            find(&["u", "s", "texture_u", "texture_s"]),
+            find(&["v", "t", "texture_v", "texture_t"]),
+        ];
+        let indices = find(&["vertex_indices", "vertex_index"]);
+        for _ in 0..element.count {
+            let mut row = vec![];
+            let mut list = vec![];
+            for (i, p) in element.properties.iter().enumerate() {
+                match p.list {
+                    Some(count) => {
+                        let n = values.next(count)? as usize;
+                        let items = (0..n)
+                            .map(|_| values.next(p.scalar))
+                            .collect::<std::io::Result<Vec<_>>>()?;
+                        if Some(i) == indices {
+                            list = items;
+                        }
+                        row.push(0.);
+                    }
+                    None => row.push(values.next(p.scalar)?),
+                }
+            }
+            let get = |i: Option<usize>| i.map(|i| row[i] as f32);
+            let vec3 = |a: [Option<usize>; 3]| match a.map(get) {
+                [Some(x), Some(y), Some(z)] => Some(Vec3::new(x, y, z)),
+                _ => None,
+            };
+            match element.name.as_str() {
+                "vertex" => {
+                    positions.push(
+                        vec3(position).ok_or_else(|| invalid("PLY vertex without position"))?,
+                    );
+                    normals.extend(vec3(normal));
+                    if let [Some(r), Some(g), Some(b)] = color {
+                        let c = |i: usize| (row[i] / element.properties[i].scalar.max()) as f32;
+                        colors.push(Color::rgb(c(r), c(g), c(b)));
+                    }
+                    if let [Some(u), Some(v)] = uv.map(get) {
+                        uvs.push((u, v));
+                    }
+                }
+                "face" => {
+                    if list.iter().any(|i| *i < 0. || i.fract() != 0.) {
+                        return Err(invalid("invalid PLY face index"));
+                    }
+                    let ids: Vec<usize> = list.iter().map(|i| *i as usize).collect();
+                    for i in 1..ids.len().saturating_sub(1) {
+                        triangles.push([ids[0], ids[i], ids[i + 1]]);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    if triangles.iter().flatten().any(|i| *i >= positions.len()) {
+        return Err(invalid("PLY face refers to missing vertex"));
+    }
+    let n = positions.len();
+    let mut mesh = Mesh::new(positions, triangles);
+    if n > 0 && normals.len() == n {
+        mesh = mesh.with_normals(normals.iter().map(|n| n.norm()).collect());
+    }
+    if n > 0 && colors.len() == n {
+        mesh = mesh.with_colors(colors);
+    }
+    if n > 0 && uvs.len() == n {
+        mesh = mesh.with_uvs(uvs);
+    }
+    Ok(mesh)
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::color::Color;
+    use crate::shape::mesh::ply::parse_ply;
+    use crate::vec3::Vec3;
+
+    #[test]
+    fn ascii() {
+        let ply = "ply
+format ascii 1.0
+comment unit square
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 255 0 0
+1 0 0 0 255 0
+1 1 0 0 0 255
+0 1 0 255 255 255
+4 0 1 2 3
+";
+        let mesh = parse_ply(ply.as_bytes()).unwrap();
+        assert_eq!(mesh.positions.len(), 4);
+        assert_eq!(mesh.triangles, vec![[0, 1, 2], [0, 2, 3]]);
+        assert_eq!(mesh.positions[2], Vec3::new(1., 1., 0.));
+        let colors = mesh.colors.unwrap();
+        assert_eq!(colors[1], Color::GREEN);
+        assert_eq!(colors[3], Color::WHITE);
+        assert!(mesh.normals.is_none());
+        let negative = ply.replace("4 0 1 2 3", "3 0 -1 2");
+        assert!(parse_ply(negative.as_bytes()).is_err());
+        let fractional = ply.replace("4 0 1 2 3", "3 0 1.5 2");
+        assert!(parse_ply(fractional.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn binary() {
+        let mut ply = b"ply
+format binary_little_endian 1.0
+element vertex 3
+property float x
+property float y
+property float z
+property float nx
+property float ny
+property float nz
+element material 1
+property list uchar ushort name
+element face 1
+property uchar flags
+property list uchar uint vertex_index
+end_header
+"
+        .to_vec();
+        for p in [[0., 0., 1.], [2., 0., 1.], [0., 2., 1.]] {
+            for v in p.iter().chain(&[0., 0., 2.]) {
+                ply.extend((*v as f32).to_le_bytes());
+            }
+        }
+        ply.extend([2, 7, 0, 9, 0]);
+        ply.extend([1, 3]);
+        for i in [0u32, 1, 2] {
+            ply.extend(i.to_le_bytes());
+        }
+        let mesh = parse_ply(&ply).unwrap();
+        assert_eq!(mesh.triangles, vec![[0, 1, 2]]);
+        assert_eq!(mesh.positions[1], Vec3::new(2., 0., 1.));
+        assert_eq!(mesh.normals.unwrap()[2], Vec3::new(0., 0., 1.));
+        assert!(mesh.colors.is_none());
+
+        assert!(parse_ply(&ply[..ply.len() - 2]).is_err());
+        assert!(parse_ply(b"ply\nformat binary_big_endian 1.0\nend_header\n").is_err());
+    }
+}
@@ -0,0 +1,118 @@
+use std::fs::read;
+use std::io::{Error, ErrorKind};
+
+use crate::shape::mesh::Mesh;
+use crate::vec3::Vec3;
+
+/// Load ASCII or binary [STL](https://www.fabbers.com/tech/STL_Format) mesh
+/// Every triangle gets its own vertices and is shaded flat, stored facet normals are ignored
+pub fn load_stl(path: &str) -> std::io::Result<Mesh> {
+    parse_stl(&read(path)?)
+}
+
+pub fn parse_stl(data: &[u8]) -> std::io::Result<Mesh> {
+    // binary files may start with "solid" too, their size tells them apart
+    let count = data
+        .get(80..84)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize);
+    let positions = match count {
+        Some(n) if data.len() == 84 + 50 * n => parse_binary(&data[84..]),
+        _ if data.starts_with(b"solid") => parse_ascii(data)?,
+        _ => return Err(invalid("not a STL file")),
+    };
+    let triangles = (0..positions.len() / 3)
+        .map(|i| [3 * i, 3 * i + 1, 3 * i + 2])
+        .collect();
+    Ok(Mesh::new(positions, triangles))
+}
+
+/// Vertices of 50 byte triangle records, after the normal and before the attribute count
+fn parse_binary(data: &[u8]) -> Vec<Vec3> {
+    let float = |b: &[u8]| f32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+    data.chunks(50)
+        .flat_map(|record| {
+            (0..3).map(move |v| {
+                let p = &record[12 + 12 * v..];
+                Vec3::new(float(p), float(&p[4..]), float(&p[8..]))
+            })
+        })
+        .collect()
+}
+
+fn parse_ascii(data: &[u8]) -> std::io::Result<Vec<Vec3>> {
+    let text = std::str::from_utf8(data).map_err(|_| invalid("invalid STL file"))?;
+    let mut tokens = text.split_ascii_whitespace();
+    let mut positions = vec![];
+    while let Some(token) = tokens.next() {
+        if token != "vertex" {
+            continue;
+        }
+        let mut v = [0.; 3];
+        for c in &mut v {
+            *c = tokens
+                .next()
+                .and_then(|t| t.parse().ok())
+                .ok_or_else(|| invalid("invalid STL vertex"))?;
+        }
+        positions.push(Vec3::new(v[0], v[1], v[2]));
+    }
+    if positions.len() % 3 != 0 {
+        return Err(invalid("STL facet without 3 vertices"));
+    }
+    Ok(positions)
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::math::approx_eq;
+    use crate::shape::mesh::stl::parse_stl;
+    use crate::shape::Shape;
+    use crate::vec3::Vec3;
+
+    #[test]
+    fn ascii() {
+        let stl = "solid square
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 1 1 0
+    endloop
+  endfacet
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 1 0
+      vertex 0 1 0
+    endloop
+  endfacet
+endsolid square
+";
+        let mesh = parse_stl(stl.as_bytes()).unwrap();
+        assert_eq!(mesh.triangles, vec![[0, 1, 2], [3, 4, 5]]);
+        assert_eq!(mesh.positions[5], Vec3::new(0., 1., 0.));
+        assert!(approx_eq(mesh.area(), 1.));
+        assert!(parse_stl(b"solid broken\nvertex 0 0\n").is_err());
+    }
+
+    #[test]
+    fn binary() {
+        // header starting with "solid" like some exporters write
+        let mut stl = b"solid binary".to_vec();
+        stl.resize(80, 0);
+        stl.extend(1u32.to_le_bytes());
+        for v in [0., 0., 1., 0., 0., 0., 2., 0., 0., 0., 2., 0.] {
+            stl.extend((v as f32).to_le_bytes());
+        }
+        stl.extend([0, 0]);
+        let mesh = parse_stl(&stl).unwrap();
+        assert_eq!(mesh.triangles, vec![[0, 1, 2]]);
+        assert_eq!(mesh.positions[1], Vec3::new(2., 0., 0.));
+        assert!(approx_eq(mesh.area(), 2.));
+        assert!(parse_stl(b"not a mesh").is_err());
+    }
+}
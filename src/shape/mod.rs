@@ -1,14 +1,386 @@
+use std::f32::consts::PI;
 use std::fmt::Debug;
 
+use crate::color::Color;
+use crate::math::solve_quadratic;
 use crate::ray::Ray;
 use crate::vec3::Vec3;
 
+pub mod capsule;
+pub mod cone;
+pub mod csg;
+pub mod cuboid;
+pub mod cylinder;
+pub mod disk;
+pub mod heightfield;
+pub mod infinite_plane;
+pub mod instance;
+pub mod mesh;
 pub mod plane;
+pub mod quad;
+pub mod sdf;
 pub mod sphere;
+pub mod torus;
+pub mod voxel;
+
+/// Minimal ray parameter of a hit, keeps reflected rays from hitting their own surface
+pub const HIT_EPSILON: f32 = 1e-4;
+
+/// Minimal cosine between the shading reflection and the geometric surface
+const GRAZING: f32 = 1e-3;
+
+/// Ray reflection off the shape surface
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Default)]
+pub struct Hit {
+    /// Reflected ray, starting at the hit point
+    pub ray: Ray,
+    pub normal: Vec3,
+    /// Surface texture coordinates, both in range [0, 1]
+    pub uv: (f32, f32),
+    /// Partial derivatives of the hit point by texture coordinates
+    /// Zero where the parametrization is degenerate, e.g. at sphere poles
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
+    /// Material index reported by shapes made of many materials, like voxel grids, 0 otherwise
+    /// Resolved by palette textures of the object material
+    pub material: usize,
+    /// Color interpolated from vertex colors of meshes having them
+    /// Resolved by vertex color textures of the object material
+    pub color: Option<Color>,
+}
+
+impl Hit {
+    /// Orthonormal tangent, bitangent and `normal`, tangent follows the u direction and
+    /// bitangent follows the v direction
+    pub fn tangent_frame(&self, normal: &Vec3) -> (Vec3, Vec3, Vec3) {
+        let along = |v: &Vec3| *v - normal.mul_n(normal.dot(v));
+        let mut tangent = along(&self.dpdu);
+        if tangent.mag() < 1e-6 {
+            tangent = orthonormal_basis(normal).0;
+        }
+        let tangent = tangent.norm();
+        let bitangent = normal.cross(&tangent);
+        if bitangent.dot(&self.dpdv) < 0. {
+            (tangent, -bitangent, *normal)
+        } else {
+            (tangent, bitangent, *normal)
+        }
+    }
+
+    /// Hit with the perturbed shading normal and the ray reflected about it
+    /// When the reflection goes below the geometric surface, or the shading normal faces
+    /// away from the incoming ray, the reflection is bent to graze the surface and the
+    /// shading normal is bent along with it, keeping them consistent
+    pub fn shade(&self, normal: &Vec3) -> Hit {
+        let incoming = self.ray.dir.reflect(&self.normal);
+        let mut dir = incoming.reflect(normal).norm();
+        let mut normal = *normal;
+        if incoming.dot(&normal) >= 0. || dir.dot(&self.normal) < GRAZING {
+            let tangential = dir - self.normal.mul_n(dir.dot(&self.normal));
+            let tangential = if tangential.mag() < 1e-6 {
+                incoming - self.normal.mul_n(incoming.dot(&self.normal))
+            } else {
+                tangential
+            };
+            dir = (tangential.norm() + self.normal.mul_n(GRAZING)).norm();
+            normal = (dir - incoming).norm();
+        }
+        Hit {
+            ray: Ray {
+                start: self.ray.start,
+                dir,
+            },
+            normal,
+            ..*self
+        }
+    }
+}
+
+/// Two unit vectors orthogonal to the unit `normal` and each other, forming a right-handed
+/// basis with it
+pub fn orthonormal_basis(normal: &Vec3) -> (Vec3, Vec3) {
+    let axis = if normal.x.abs() < 0.9 {
+        Vec3::new(1., 0., 0.)
+    } else {
+        Vec3::new(0., 1., 0.)
+    };
+    let t = (axis - normal.mul_n(normal.dot(&axis))).norm();
+    (t, normal.cross(&t))
+}
+
+/// Intersection of the ray with the plane through `point`, along with the ray parameter and
+/// the plane normal facing the ray
+pub fn intersect_plane(ray: &Ray, point: &Vec3, normal: &Vec3) -> Option<(f32, Vec3, Vec3)> {
+    let denom = normal.dot(&ray.dir);
+    if denom.abs() < 1e-8 {
+        return None;
+    }
+    let t = (*point - ray.start).dot(normal) / denom;
+    if t < HIT_EPSILON {
+        return None;
+    }
+    let facing = if denom > 0. { -*normal } else { *normal };
+    Some((t, ray.with_param(t), facing))
+}
+
+/// Orthonormal frame with z axis along the shape axis, shapes with an axis of symmetry are
+/// intersected in it
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Frame {
+    pub origin: Vec3,
+    pub x: Vec3,
+    pub y: Vec3,
+    pub z: Vec3,
+}
+
+impl Frame {
+    /// Frame at `origin` with z along the unit `axis`
+    pub fn new(origin: Vec3, axis: Vec3) -> Frame {
+        let (x, y) = orthonormal_basis(&axis);
+        Frame {
+            origin,
+            x,
+            y,
+            z: axis,
+        }
+    }
+
+    pub fn local_ray(&self, ray: &Ray) -> Ray {
+        let local = |v: Vec3| Vec3::new(v.dot(&self.x), v.dot(&self.y), v.dot(&self.z));
+        Ray {
+            start: local(ray.start - self.origin),
+            dir: local(ray.dir),
+        }
+    }
+
+    /// World direction of the local vector
+    pub fn vector(&self, v: &Vec3) -> Vec3 {
+        self.x.mul_n(v.x) + self.y.mul_n(v.y) + self.z.mul_n(v.z)
+    }
+
+    /// World crossing of the local one, ray parameter is kept since the frame is orthonormal
+    pub fn crossing(&self, c: &Crossing) -> Crossing {
+        Crossing {
+            normal: self.vector(&c.normal),
+            dpdu: self.vector(&c.dpdu),
+            dpdv: self.vector(&c.dpdv),
+            ..*c
+        }
+    }
+
+    /// World crossings of the local ones
+    pub fn crossings(&self, local: impl IntoIterator<Item = Crossing>) -> Vec<Crossing> {
+        local.into_iter().map(|c| self.crossing(&c)).collect()
+    }
+}
+
+/// Ray crossing the shape surface at the ray parameter `t`, `normal` is the outward surface
+/// normal
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Crossing {
+    pub t: f32,
+    pub normal: Vec3,
+    pub uv: (f32, f32),
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
+}
+
+impl Crossing {
+    /// Reflection of the ray off the surface at the crossing
+    pub fn hit(&self, ray: &Ray) -> Hit {
+        let mut normal = self.normal.norm();
+        if normal.dot(&ray.dir) > 0. {
+            normal = -normal;
+        }
+        Hit {
+            ray: Ray {
+                start: ray.with_param(self.t),
+                dir: ray.dir.reflect(&normal).norm(),
+            },
+            normal,
+            uv: self.uv,
+            dpdu: self.dpdu,
+            dpdv: self.dpdv,
+            ..Default::default()
+        }
+    }
+}
+
+/// Reflection of the ray off the closest crossing in front of it
+pub fn closest_hit(ray: &Ray, crossings: impl IntoIterator<Item = Crossing>) -> Option<Hit> {
+    crossings
+        .into_iter()
+        .filter(|c| c.t >= HIT_EPSILON)
+        .min_by(|a, b| a.t.total_cmp(&b.t))
+        .map(|c| c.hit(ray))
+}
+
+/// Crossings of a closed surface paired into entries and exits, ordered along the ray
+pub fn spans(mut crossings: Vec<Crossing>) -> Vec<(Crossing, Crossing)> {
+    crossings.sort_by(|a, b| a.t.total_cmp(&b.t));
+    crossings.chunks_exact(2).map(|c| (c[0], c[1])).collect()
+}
+
+/// Ray parameter and barycentric coordinates of `b` and `c` where the ray crosses the triangle,
+/// from either side
+/// [ref](https://en.wikipedia.org/wiki/M%C3%B6ller%E2%80%93Trumbore_intersection_algorithm)
+pub fn intersect_triangle(ray: &Ray, a: &Vec3, b: &Vec3, c: &Vec3) -> Option<(f32, f32, f32)> {
+    let (e1, e2) = (*b - *a, *c - *a);
+    let p = ray.dir.cross(&e2);
+    let det = e1.dot(&p);
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let inv = 1. / det;
+    let s = ray.start - *a;
+    let u = s.dot(&p) * inv;
+    if !(0. ..=1.).contains(&u) {
+        return None;
+    }
+    let q = s.cross(&e1);
+    let v = ray.dir.dot(&q) * inv;
+    if v < 0. || u + v > 1. {
+        return None;
+    }
+    Some((e2.dot(&q) * inv, u, v))
+}
+
+/// Angle of the point around the z axis in range [0, 1], along with its derivative of the point
+pub fn azimuth(p: &Vec3) -> (f32, Vec3) {
+    let u = p.y.atan2(p.x).rem_euclid(2. * PI) / (2. * PI);
+    (u, Vec3::new(-p.y, p.x, 0.).mul_n(2. * PI))
+}
+
+/// Ray parameters where the ray crosses the sphere
+pub fn sphere_roots(ray: &Ray, center: &Vec3, radius: f32) -> Vec<f32> {
+    let s = ray.start - *center;
+    solve_quadratic(
+        ray.dir.dot(&ray.dir) as f64,
+        2. * s.dot(&ray.dir) as f64,
+        (s.dot(&s) - radius * radius) as f64,
+    )
+    .into_iter()
+    .map(|t| t as f32)
+    .collect()
+}
+
+/// Ray parameters where the ray crosses the infinite cylinder around the z axis
+pub fn cylinder_roots(ray: &Ray, radius: f32) -> Vec<f32> {
+    let (s, d) = (ray.start, ray.dir);
+    solve_quadratic(
+        (d.x * d.x + d.y * d.y) as f64,
+        2. * (s.x * d.x + s.y * d.y) as f64,
+        (s.x * s.x + s.y * s.y - radius * radius) as f64,
+    )
+    .into_iter()
+    .map(|t| t as f32)
+    .collect()
+}
+
+/// Hit of the disk cap perpendicular to the z axis at the `z` height, facing `side`
+pub fn cap_hit(ray: &Ray, z: f32, radius: f32, side: f32) -> Option<Crossing> {
+    if ray.dir.z == 0. {
+        return None;
+    }
+    let t = (z - ray.start.z) / ray.dir.z;
+    let p = ray.with_param(t);
+    let r = (p.x * p.x + p.y * p.y).sqrt();
+    if r > radius {
+        return None;
+    }
+    let (u, dpdu) = azimuth(&p);
+    let radial = if r > 0. {
+        Vec3::new(p.x / r, p.y / r, 0.)
+    } else {
+        Vec3::new(1., 0., 0.)
+    };
+    Some(Crossing {
+        t,
+        normal: Vec3::new(0., 0., side),
+        uv: (u, r / radius),
+        dpdu,
+        dpdv: radial.mul_n(radius),
+    })
+}
+
+/// Components of the vectors, for shape fingerprints
+pub fn components(vectors: &[Vec3]) -> impl Iterator<Item = f32> + '_ {
+    vectors.iter().flat_map(|v| [v.x, v.y, v.z])
+}
 
 pub trait Shape: Debug + Send + Sync {
     /// Reflect a ray of the shape's surface
-    fn reflect(&self, ray: &Ray) -> Option<(Ray, Vec3)>;
+    fn reflect(&self, ray: &Ray) -> Option<Hit>;
 
     fn center(&self) -> Vec3;
+
+    /// Surface area, infinite for unbounded shapes
+    fn area(&self) -> f32;
+
+    /// Push the parameters defining the surface, shapes with equal fingerprints reflect rays
+    /// the same
+    fn fingerprint(&self, out: &mut Vec<f32>);
+
+    /// Intervals of the ray inside the solid, ordered along the ray, including the ones behind
+    /// the ray start
+    /// Empty for open surfaces, which have no inside
+    fn spans(&self, ray: &Ray) -> Vec<(Crossing, Crossing)> {
+        let _ = ray;
+        vec![]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::math::approx_eq;
+    use crate::ray::Ray;
+    use crate::shape::Hit;
+    use crate::vec3::Vec3;
+
+    fn hit() -> Hit {
+        Hit {
+            ray: Ray {
+                start: Vec3::zero(),
+                dir: Vec3::new(1., 0., 1.).norm(),
+            },
+            normal: Vec3::new(0., 0., 1.),
+            dpdu: Vec3::new(2., 0., 0.5),
+            dpdv: Vec3::new(0., -3., 0.),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn tangent_frame() {
+        let (t, b, n) = hit().tangent_frame(&Vec3::new(0., 0., 1.));
+        assert!(t.approx_eq(&Vec3::new(1., 0., 0.)));
+        assert!(b.approx_eq(&Vec3::new(0., -1., 0.)));
+        assert!(n.approx_eq(&Vec3::new(0., 0., 1.)));
+
+        let degenerate = Hit {
+            dpdu: Vec3::zero(),
+            ..hit()
+        };
+        let (t, b, n) = degenerate.tangent_frame(&Vec3::new(0., 0., 1.));
+        assert!(approx_eq(t.dot(&n), 0.) && approx_eq(b.dot(&n), 0.));
+        assert!(approx_eq(t.mag(), 1.) && approx_eq(b.mag(), 1.));
+    }
+
+    #[test]
+    fn shade() {
+        let h = hit();
+        let same = h.shade(&h.normal);
+        assert!(same.ray.dir.approx_eq(&h.ray.dir));
+
+        let n = Vec3::new(-1., 0., 2.).norm();
+        let tilted = h.shade(&n);
+        assert_eq!(tilted.normal, n);
+        assert!(tilted.ray.dir.approx_eq(&Vec3::new(-0.141, 0., 0.99)));
+
+        // reflection about this normal would go below the surface
+        let below = h.shade(&Vec3::new(-1., 0., 0.3).norm());
+        assert!(below.ray.dir.dot(&h.normal) > 0.);
+        let incoming = h.ray.dir.reflect(&h.normal);
+        assert!(incoming.reflect(&below.normal).approx_eq(&below.ray.dir));
+    }
 }
@@ -0,0 +1,109 @@
+use crate::ray::Ray;
+use crate::shape::{components, intersect_plane, Hit, Shape};
+use crate::vec3::Vec3;
+
+/// Two-sided parallelogram spanned by edges `u` and `v` from the `corner`
+/// Texture coordinates follow the edges
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Quad {
+    pub corner: Vec3,
+    pub u: Vec3,
+    pub v: Vec3,
+}
+
+impl Quad {
+    /// Rectangle centered at `center` with sides of `size` along the unit `right` and `up`
+    pub fn rect(center: Vec3, right: Vec3, up: Vec3, size: (f32, f32)) -> Quad {
+        let (u, v) = (right.mul_n(size.0), up.mul_n(size.1));
+        Quad {
+            corner: center - (u + v).mul_n(0.5),
+            u,
+            v,
+        }
+    }
+}
+
+impl Shape for Quad {
+    /// [ref](https://raytracing.github.io/books/RayTracingTheNextWeek.html#quadrilaterals)
+    fn reflect(&self, ray: &Ray) -> Option<Hit> {
+        let n = self.u.cross(&self.v);
+        let (_, p, normal) = intersect_plane(ray, &self.corner, &n.norm())?;
+        let w = n.mul_n(1. / n.dot(&n));
+        let q = p - self.corner;
+        let (a, b) = (w.dot(&q.cross(&self.v)), w.dot(&self.u.cross(&q)));
+        if !(0. ..=1.).contains(&a) || !(0. ..=1.).contains(&b) {
+            return None;
+        }
+        Some(Hit {
+            ray: Ray {
+                start: p,
+                dir: ray.dir.reflect(&normal).norm(),
+            },
+            normal,
+            uv: (a, b),
+            dpdu: self.u,
+            dpdv: self.v,
+            ..Default::default()
+        })
+    }
+
+    fn center(&self) -> Vec3 {
+        self.corner + (self.u + self.v).mul_n(0.5)
+    }
+
+    fn area(&self) -> f32 {
+        self.u.cross(&self.v).mag()
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.extend(components(&[self.corner, self.u, self.v]));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::math::approx_eq;
+    use crate::ray::Ray;
+    use crate::shape::quad::Quad;
+    use crate::shape::Shape;
+    use crate::vec3::Vec3;
+
+    fn quad() -> Quad {
+        Quad::rect(
+            Vec3::new(0., 0., 2.),
+            Vec3::new(1., 0., 0.),
+            Vec3::new(0., 1., 0.),
+            (2., 4.),
+        )
+    }
+
+    #[test]
+    fn rect() {
+        let q = quad();
+        assert!(q.corner.approx_eq(&Vec3::new(-1., -2., 2.)));
+        assert!(q.center().approx_eq(&Vec3::new(0., 0., 2.)));
+        assert!(approx_eq(q.area(), 8.));
+    }
+
+    #[test]
+    fn reflect() {
+        let r = Ray {
+            start: Vec3::new(0.5, 1., 0.),
+            dir: Vec3::new(0., 0., 1.),
+        };
+        let hit = quad().reflect(&r).unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(0.5, 1., 2.)));
+        assert!(hit.ray.dir.approx_eq(&Vec3::new(0., 0., -1.)));
+        assert!(hit.normal.approx_eq(&Vec3::new(0., 0., -1.)));
+        assert!(approx_eq(hit.uv.0, 0.75) && approx_eq(hit.uv.1, 0.75));
+    }
+
+    #[test]
+    fn miss_outside() {
+        let r = Ray {
+            start: Vec3::new(1.5, 0., 0.),
+            dir: Vec3::new(0., 0., 1.),
+        };
+        assert!(quad().reflect(&r).is_none());
+    }
+}
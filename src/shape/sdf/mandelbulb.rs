@@ -0,0 +1,84 @@
+use crate::shape::sdf::Sdf;
+use crate::vec3::Vec3;
+
+/// [Mandelbulb](https://en.wikipedia.org/wiki/Mandelbulb) fractal, fits in the sphere of
+/// radius 1.2 for the power of 8
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Mandelbulb {
+    pub power: f32,
+    pub iterations: usize,
+}
+
+impl Default for Mandelbulb {
+    fn default() -> Self {
+        Mandelbulb {
+            power: 8.,
+            iterations: 12,
+        }
+    }
+}
+
+impl Sdf for Mandelbulb {
+    /// Distance estimate from the running derivative of the iteration
+    /// [ref](http://blog.hvidtfeldts.net/index.php/2011/09/distance-estimated-3d-fractals-v-the-mandelbulb-different-de-approximations/)
+    fn distance(&self, p: Vec3) -> f32 {
+        let mut z = p;
+        let mut dr = 1.;
+        let mut r = z.mag();
+        for _ in 0..self.iterations {
+            if r > 2. {
+                break;
+            }
+            let theta = if r > 0. { (z.z / r).acos() } else { 0. };
+            let phi = z.y.atan2(z.x);
+            dr = r.powf(self.power - 1.) * self.power * dr + 1.;
+            let (theta, phi) = (theta * self.power, phi * self.power);
+            z = Vec3::new(
+                theta.sin() * phi.cos(),
+                theta.sin() * phi.sin(),
+                theta.cos(),
+            )
+            .mul_n(r.powf(self.power))
+                + p;
+            r = z.mag();
+        }
+        // bounded orbits of the inside points give estimates close to zero
+        let r = r.max(f32::MIN_POSITIVE);
+        0.5 * r.ln() * r / dr
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.push(5.);
+        out.extend([self.power, self.iterations as f32]);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ray::Ray;
+    use crate::shape::sdf::mandelbulb::Mandelbulb;
+    use crate::shape::sdf::{Sdf, SdfShape};
+    use crate::shape::Shape;
+    use crate::vec3::Vec3;
+
+    #[test]
+    fn distance() {
+        let m = Mandelbulb::default();
+        assert!(m.distance(Vec3::zero()).abs() < 1e-6);
+        assert!(m.distance(Vec3::new(2., 0., 0.)) > 0.5);
+        assert!(m.distance(Vec3::new(0., 0., 1.5)) > 0.);
+    }
+
+    #[test]
+    fn reflect() {
+        let s = SdfShape::new(Mandelbulb::default(), 1.2);
+        let r = Ray {
+            start: Vec3::new(0., 0., 5.),
+            dir: Vec3::new(0., 0., -1.),
+        };
+        let hit = s.reflect(&r).unwrap();
+        assert!(hit.ray.start.z > 0.5 && hit.ray.start.z < 1.2, "{hit:?}");
+        assert!(hit.normal.z > 0.);
+        assert!(s.distance(hit.ray.start).abs() < 1e-3);
+    }
+}
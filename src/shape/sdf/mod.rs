@@ -0,0 +1,302 @@
+use std::fmt::Debug;
+
+use crate::ray::Ray;
+use crate::shape::sdf::op::{
+    Bend, Intersection, Repeat, Round, SmoothUnion, Subtraction, Translate, Twist, Union,
+};
+use crate::shape::sphere::sphere_uv;
+use crate::shape::{components, sphere_roots, Hit, Shape, HIT_EPSILON};
+use crate::vec3::Vec3;
+
+pub mod mandelbulb;
+pub mod op;
+pub mod primitive;
+
+/// Signed distance field, negative inside of the surface
+/// Distance must not overestimate the distance to the surface, otherwise sphere tracing can
+/// step through it
+pub trait Sdf: Debug + Send + Sync {
+    fn distance(&self, p: Vec3) -> f32;
+
+    /// Push the parameters defining the field after the number unique to the field type, which
+    /// tells apart fields with the same parameter count
+    fn fingerprint(&self, out: &mut Vec<f32>);
+
+    fn translate(self, offset: Vec3) -> Translate
+    where
+        Self: Sized + 'static,
+    {
+        Translate {
+            sdf: Box::new(self),
+            offset,
+        }
+    }
+
+    fn union(self, other: impl Sdf + 'static) -> Union
+    where
+        Self: Sized + 'static,
+    {
+        Union {
+            a: Box::new(self),
+            b: Box::new(other),
+        }
+    }
+
+    /// Union blending the surfaces within the distance `k`
+    fn smooth_union(self, other: impl Sdf + 'static, k: f32) -> SmoothUnion
+    where
+        Self: Sized + 'static,
+    {
+        SmoothUnion {
+            a: Box::new(self),
+            b: Box::new(other),
+            k,
+        }
+    }
+
+    fn intersection(self, other: impl Sdf + 'static) -> Intersection
+    where
+        Self: Sized + 'static,
+    {
+        Intersection {
+            a: Box::new(self),
+            b: Box::new(other),
+        }
+    }
+
+    /// Remove `other` from the shape, blending the cut within the distance `k`, sharp when 0
+    fn subtract(self, other: impl Sdf + 'static, k: f32) -> Subtraction
+    where
+        Self: Sized + 'static,
+    {
+        Subtraction {
+            a: Box::new(self),
+            b: Box::new(other),
+            k,
+        }
+    }
+
+    /// Rotate around the z axis by `rate` radians per unit of height
+    fn twist(self, rate: f32) -> Twist
+    where
+        Self: Sized + 'static,
+    {
+        Twist {
+            sdf: Box::new(self),
+            rate,
+        }
+    }
+
+    /// Bend the x axis towards the y axis by `rate` radians per unit of length
+    fn bend(self, rate: f32) -> Bend
+    where
+        Self: Sized + 'static,
+    {
+        Bend {
+            sdf: Box::new(self),
+            rate,
+        }
+    }
+
+    /// Repeat the shape infinitely with the `period` along every axis, zero disables the axis
+    fn repeat(self, period: Vec3) -> Repeat
+    where
+        Self: Sized + 'static,
+    {
+        Repeat {
+            sdf: Box::new(self),
+            period,
+        }
+    }
+
+    /// Inflate the surface by the `radius`, rounding its edges
+    fn round(self, radius: f32) -> Round
+    where
+        Self: Sized + 'static,
+    {
+        Round {
+            sdf: Box::new(self),
+            radius,
+        }
+    }
+}
+
+/// Shape of the signed distance field surface, intersected by
+/// [sphere tracing](https://iquilezles.org/articles/raymarchingdf/)
+/// Texture coordinates are spherical coordinates of the normal
+#[derive(Debug)]
+pub struct SdfShape {
+    pub sdf: Box<dyn Sdf>,
+    /// Position of the field origin
+    pub position: Vec3,
+    /// Radius of the sphere around the position enclosing the surface, rays are traced in it
+    pub bound: f32,
+    pub max_steps: usize,
+    /// Distance at which the surface is considered hit
+    pub epsilon: f32,
+    /// Step length multiplier, values below 1 keep fields distorted by twist or bend from
+    /// being stepped through
+    pub step_scale: f32,
+}
+
+impl SdfShape {
+    pub fn new(sdf: impl Sdf + 'static, bound: f32) -> SdfShape {
+        SdfShape {
+            sdf: Box::new(sdf),
+            position: Vec3::zero(),
+            bound,
+            max_steps: 256,
+            epsilon: 1e-4,
+            step_scale: 1.,
+        }
+    }
+
+    pub fn distance(&self, p: Vec3) -> f32 {
+        self.sdf.distance(p - self.position)
+    }
+
+    /// Gradient of the field by central differences on the tetrahedron vertices
+    /// [ref](https://iquilezles.org/articles/normalsSDF/)
+    pub fn normal(&self, p: Vec3) -> Vec3 {
+        let h = self.epsilon;
+        [
+            Vec3::new(1., -1., -1.),
+            Vec3::new(-1., -1., 1.),
+            Vec3::new(-1., 1., -1.),
+            Vec3::new(1., 1., 1.),
+        ]
+        .iter()
+        .fold(Vec3::zero(), |n, k| {
+            n + k.mul_n(self.distance(p + k.mul_n(h)))
+        })
+        .norm()
+    }
+}
+
+impl Shape for SdfShape {
+    fn reflect(&self, ray: &Ray) -> Option<Hit> {
+        let bounds = sphere_roots(ray, &self.position, self.bound);
+        let (&enter, &exit) = (bounds.first()?, bounds.last()?);
+        let mut t = enter.max(HIT_EPSILON);
+        // rays starting on the surface they are reflected from step off it before hits count,
+        // grazing ones stay within `epsilon` of it for many steps
+        let mut leaving = enter < HIT_EPSILON;
+        for _ in 0..self.max_steps {
+            if t > exit {
+                return None;
+            }
+            let p = ray.with_param(t);
+            // ray starting inside traces the surface from within
+            let d = self.distance(p).abs();
+            if d < self.epsilon && leaving {
+                t += self.epsilon;
+                continue;
+            }
+            leaving = false;
+            if d < self.epsilon {
+                let mut normal = self.normal(p);
+                if normal.dot(&ray.dir) > 0. {
+                    normal = -normal;
+                }
+                return Some(Hit {
+                    ray: Ray {
+                        start: p,
+                        dir: ray.dir.reflect(&normal).norm(),
+                    },
+                    normal,
+                    uv: sphere_uv(&normal),
+                    ..Default::default()
+                });
+            }
+            t += d * self.step_scale;
+        }
+        None
+    }
+
+    fn center(&self) -> Vec3 {
+        self.position
+    }
+
+    /// Area of the bounding sphere, the surface area of the field is unknown
+    fn area(&self) -> f32 {
+        4. * std::f32::consts::PI * self.bound * self.bound
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.extend(components(&[self.position]));
+        out.extend([
+            self.bound,
+            self.max_steps as f32,
+            self.epsilon,
+            self.step_scale,
+        ]);
+        self.sdf.fingerprint(out);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ray::Ray;
+    use crate::shape::sdf::primitive::{SdfBox, SdfSphere};
+    use crate::shape::sdf::{Sdf, SdfShape};
+    use crate::shape::Shape;
+    use crate::vec3::Vec3;
+
+    fn ray(start: Vec3, dir: Vec3) -> Ray {
+        Ray {
+            start,
+            dir: dir.norm(),
+        }
+    }
+
+    #[test]
+    fn reflect() {
+        let mut s = SdfShape::new(SdfSphere { radius: 1. }, 1.5);
+        s.position = Vec3::new(0., 0., 2.);
+        let hit = s
+            .reflect(&ray(Vec3::new(-5., 0., 2.), Vec3::new(1., 0., 0.)))
+            .unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(-1., 0., 2.)));
+        assert!(hit.normal.approx_eq(&Vec3::new(-1., 0., 0.)));
+        assert!(hit.ray.dir.approx_eq(&Vec3::new(-1., 0., 0.)));
+        // reflected ray leaves the surface
+        let next = Ray {
+            start: hit.ray.start,
+            dir: hit.ray.dir,
+        };
+        assert!(s.reflect(&next).is_none());
+        // grazing reflection at 2 degrees stays close to the surface at first
+        let a = 2f32.to_radians();
+        let grazing = ray(Vec3::new(-1., 0., 2.), Vec3::new(-a.sin(), a.cos(), 0.));
+        assert!(s.reflect(&grazing).is_none());
+    }
+
+    #[test]
+    fn reflect_inside() {
+        let s = SdfShape::new(
+            SdfBox {
+                half_size: Vec3::diag(1.),
+            },
+            2.,
+        );
+        let hit = s
+            .reflect(&ray(Vec3::zero(), Vec3::new(0., 1., 0.)))
+            .unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(0., 1., 0.)));
+        assert!(hit.normal.approx_eq(&Vec3::new(0., -1., 0.)));
+    }
+
+    #[test]
+    fn miss() {
+        let s = SdfShape::new(
+            SdfSphere { radius: 1. }.translate(Vec3::new(0., 0., 0.5)),
+            2.,
+        );
+        assert!(s
+            .reflect(&ray(Vec3::new(-5., 0., 1.6), Vec3::new(1., 0., 0.)))
+            .is_none());
+        assert!(s
+            .reflect(&ray(Vec3::new(-5., 0., 0.), Vec3::new(-1., 0., 0.)))
+            .is_none());
+    }
+}
@@ -0,0 +1,258 @@
+use crate::shape::components;
+use crate::shape::sdf::Sdf;
+use crate::vec3::Vec3;
+
+/// [ref](https://iquilezles.org/articles/distfunctions/)
+#[derive(Debug)]
+pub struct Translate {
+    pub sdf: Box<dyn Sdf>,
+    pub offset: Vec3,
+}
+
+impl Sdf for Translate {
+    fn distance(&self, p: Vec3) -> f32 {
+        self.sdf.distance(p - self.offset)
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.push(6.);
+        out.extend(components(&[self.offset]));
+        self.sdf.fingerprint(out);
+    }
+}
+
+#[derive(Debug)]
+pub struct Union {
+    pub a: Box<dyn Sdf>,
+    pub b: Box<dyn Sdf>,
+}
+
+impl Sdf for Union {
+    fn distance(&self, p: Vec3) -> f32 {
+        self.a.distance(p).min(self.b.distance(p))
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.push(7.);
+        self.a.fingerprint(out);
+        self.b.fingerprint(out);
+    }
+}
+
+#[derive(Debug)]
+pub struct Intersection {
+    pub a: Box<dyn Sdf>,
+    pub b: Box<dyn Sdf>,
+}
+
+impl Sdf for Intersection {
+    fn distance(&self, p: Vec3) -> f32 {
+        self.a.distance(p).max(self.b.distance(p))
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.push(8.);
+        self.a.fingerprint(out);
+        self.b.fingerprint(out);
+    }
+}
+
+/// Polynomial smooth minimum of distances, blending within the distance `k`
+/// [ref](https://iquilezles.org/articles/smin/)
+pub fn smooth_min(a: f32, b: f32, k: f32) -> f32 {
+    if k <= 0. {
+        return a.min(b);
+    }
+    let h = (0.5 + 0.5 * (b - a) / k).clamp(0., 1.);
+    b + (a - b) * h - k * h * (1. - h)
+}
+
+#[derive(Debug)]
+pub struct SmoothUnion {
+    pub a: Box<dyn Sdf>,
+    pub b: Box<dyn Sdf>,
+    pub k: f32,
+}
+
+impl Sdf for SmoothUnion {
+    fn distance(&self, p: Vec3) -> f32 {
+        smooth_min(self.a.distance(p), self.b.distance(p), self.k)
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.push(9.);
+        out.push(self.k);
+        self.a.fingerprint(out);
+        self.b.fingerprint(out);
+    }
+}
+
+/// `a` with `b` cut out of it, smooth within the distance `k`
+#[derive(Debug)]
+pub struct Subtraction {
+    pub a: Box<dyn Sdf>,
+    pub b: Box<dyn Sdf>,
+    pub k: f32,
+}
+
+impl Sdf for Subtraction {
+    fn distance(&self, p: Vec3) -> f32 {
+        -smooth_min(-self.a.distance(p), self.b.distance(p), self.k)
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.push(10.);
+        out.push(self.k);
+        self.a.fingerprint(out);
+        self.b.fingerprint(out);
+    }
+}
+
+/// Distorts the field, distances are underestimated only for small rates
+#[derive(Debug)]
+pub struct Twist {
+    pub sdf: Box<dyn Sdf>,
+    pub rate: f32,
+}
+
+impl Sdf for Twist {
+    fn distance(&self, p: Vec3) -> f32 {
+        self.sdf.distance(p.rotate_z(-self.rate * p.z))
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.push(11.);
+        out.push(self.rate);
+        self.sdf.fingerprint(out);
+    }
+}
+
+/// Distorts the field, distances are underestimated only for small rates
+#[derive(Debug)]
+pub struct Bend {
+    pub sdf: Box<dyn Sdf>,
+    pub rate: f32,
+}
+
+impl Sdf for Bend {
+    fn distance(&self, p: Vec3) -> f32 {
+        let bent = Vec3::new(p.x, p.y, 0.).rotate_z(-self.rate * p.x);
+        self.sdf.distance(bent.with_z(p.z))
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.push(12.);
+        out.push(self.rate);
+        self.sdf.fingerprint(out);
+    }
+}
+
+/// Repetition is exact only while the shape fits in its period cell
+#[derive(Debug)]
+pub struct Repeat {
+    pub sdf: Box<dyn Sdf>,
+    pub period: Vec3,
+}
+
+impl Sdf for Repeat {
+    fn distance(&self, p: Vec3) -> f32 {
+        let cell = |v: f32, period: f32| {
+            if period > 0. {
+                v - period * (v / period).round()
+            } else {
+                v
+            }
+        };
+        self.sdf.distance(Vec3::new(
+            cell(p.x, self.period.x),
+            cell(p.y, self.period.y),
+            cell(p.z, self.period.z),
+        ))
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.push(13.);
+        out.extend(components(&[self.period]));
+        self.sdf.fingerprint(out);
+    }
+}
+
+#[derive(Debug)]
+pub struct Round {
+    pub sdf: Box<dyn Sdf>,
+    pub radius: f32,
+}
+
+impl Sdf for Round {
+    fn distance(&self, p: Vec3) -> f32 {
+        self.sdf.distance(p) - self.radius
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.push(14.);
+        out.push(self.radius);
+        self.sdf.fingerprint(out);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f32::consts::PI;
+
+    use crate::math::approx_eq;
+    use crate::shape::sdf::op::smooth_min;
+    use crate::shape::sdf::primitive::{SdfBox, SdfSphere};
+    use crate::shape::sdf::Sdf;
+    use crate::vec3::Vec3;
+
+    fn sphere(x: f32) -> impl Sdf {
+        SdfSphere { radius: 1. }.translate(Vec3::new(x, 0., 0.))
+    }
+
+    #[test]
+    fn combine() {
+        let p = Vec3::new(0., 0., 0.);
+        assert!(approx_eq(sphere(-0.5).union(sphere(0.5)).distance(p), -0.5));
+        assert!(approx_eq(
+            sphere(-0.5).intersection(sphere(3.)).distance(p),
+            2.
+        ));
+        assert!(approx_eq(
+            sphere(-0.5).subtract(sphere(0.5), 0.).distance(p),
+            0.5
+        ));
+        // blended surface bulges out between the spheres
+        let smooth = sphere(-1.5).smooth_union(sphere(1.5), 0.5).distance(p);
+        assert!(smooth < 0.5 && smooth > 0.3);
+        assert_eq!(smooth_min(1., 5., 0.5), 1.);
+    }
+
+    #[test]
+    fn distort() {
+        let bar = SdfBox {
+            half_size: Vec3::new(2., 0.1, 10.),
+        };
+        // quarter turn at the height of 1
+        let twisted = bar.twist(PI / 2.);
+        assert!(twisted.distance(Vec3::new(0., 1.5, 1.)) < 0.);
+        assert!(twisted.distance(Vec3::new(1.5, 0., 0.)) < 0.);
+
+        let repeated = SdfSphere { radius: 1. }.repeat(Vec3::new(4., 0., 0.));
+        assert!(approx_eq(repeated.distance(Vec3::new(8., 0., 0.)), -1.));
+        assert!(approx_eq(repeated.distance(Vec3::new(10., 0., 0.)), 1.));
+        assert!(approx_eq(repeated.distance(Vec3::new(0., 5., 0.)), 4.));
+
+        let rounded = SdfBox {
+            half_size: Vec3::diag(1.),
+        }
+        .round(0.5);
+        assert!(approx_eq(rounded.distance(Vec3::new(3., 0., 0.)), 1.5));
+
+        let bent = SdfBox {
+            half_size: Vec3::new(10., 0.1, 0.1),
+        }
+        .bend(0.5);
+        assert!(approx_eq(bent.distance(Vec3::zero()), -0.1));
+        assert!(bent.distance(Vec3::new(1., 0., 0.)) > 0.);
+    }
+}
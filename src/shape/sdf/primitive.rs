@@ -0,0 +1,133 @@
+use crate::shape::components;
+use crate::shape::sdf::Sdf;
+use crate::vec3::Vec3;
+
+/// [ref](https://iquilezles.org/articles/distfunctions/)
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct SdfSphere {
+    pub radius: f32,
+}
+
+impl Sdf for SdfSphere {
+    fn distance(&self, p: Vec3) -> f32 {
+        p.mag() - self.radius
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.push(0.);
+        out.push(self.radius);
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct SdfBox {
+    pub half_size: Vec3,
+}
+
+impl Sdf for SdfBox {
+    fn distance(&self, p: Vec3) -> f32 {
+        let q = p.abs() - self.half_size;
+        let outside = Vec3::new(q.x.max(0.), q.y.max(0.), q.z.max(0.)).mag();
+        outside + q.x.max(q.y).max(q.z).min(0.)
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.push(1.);
+        out.extend(components(&[self.half_size]));
+    }
+}
+
+/// Torus in the xy plane
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct SdfTorus {
+    pub major_radius: f32,
+    pub minor_radius: f32,
+}
+
+impl Sdf for SdfTorus {
+    fn distance(&self, p: Vec3) -> f32 {
+        let rho = (p.x * p.x + p.y * p.y).sqrt() - self.major_radius;
+        (rho * rho + p.z * p.z).sqrt() - self.minor_radius
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.push(2.);
+        out.extend([self.major_radius, self.minor_radius]);
+    }
+}
+
+/// Capped cylinder along the z axis, centered at the origin
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct SdfCylinder {
+    pub radius: f32,
+    pub half_height: f32,
+}
+
+impl Sdf for SdfCylinder {
+    fn distance(&self, p: Vec3) -> f32 {
+        let dx = (p.x * p.x + p.y * p.y).sqrt() - self.radius;
+        let dz = p.z.abs() - self.half_height;
+        dx.max(dz).min(0.) + (dx.max(0.).powi(2) + dz.max(0.).powi(2)).sqrt()
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.push(3.);
+        out.extend([self.radius, self.half_height]);
+    }
+}
+
+/// Half-space below the plane through the origin with the unit `normal`
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct SdfPlane {
+    pub normal: Vec3,
+}
+
+impl Sdf for SdfPlane {
+    fn distance(&self, p: Vec3) -> f32 {
+        p.dot(&self.normal)
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.push(4.);
+        out.extend(components(&[self.normal]));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::math::approx_eq;
+    use crate::shape::sdf::primitive::{SdfBox, SdfCylinder, SdfSphere, SdfTorus};
+    use crate::shape::sdf::Sdf;
+    use crate::vec3::Vec3;
+
+    #[test]
+    fn distances() {
+        let sphere = SdfSphere { radius: 1. };
+        assert!(approx_eq(sphere.distance(Vec3::new(3., 0., 0.)), 2.));
+        assert!(approx_eq(sphere.distance(Vec3::zero()), -1.));
+
+        let cube = SdfBox {
+            half_size: Vec3::diag(1.),
+        };
+        assert!(approx_eq(cube.distance(Vec3::new(2., 2., 0.)), 2f32.sqrt()));
+        assert!(approx_eq(cube.distance(Vec3::new(0.5, 0., 0.)), -0.5));
+
+        let torus = SdfTorus {
+            major_radius: 2.,
+            minor_radius: 0.5,
+        };
+        assert!(approx_eq(torus.distance(Vec3::zero()), 1.5));
+        assert!(approx_eq(torus.distance(Vec3::new(0., 2., 1.)), 0.5));
+
+        let cylinder = SdfCylinder {
+            radius: 1.,
+            half_height: 1.,
+        };
+        assert!(approx_eq(cylinder.distance(Vec3::new(0., 0., 3.)), 2.));
+        assert!(approx_eq(cylinder.distance(Vec3::new(4., 0., 0.)), 3.));
+        assert!(approx_eq(
+            cylinder.distance(Vec3::new(2., 0., 2.)),
+            2f32.sqrt()
+        ));
+    }
+}
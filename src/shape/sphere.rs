@@ -1,6 +1,8 @@
+use std::f32::consts::PI;
+
 use crate::math::sq_diff_root;
 use crate::ray::Ray;
-use crate::shape::Shape;
+use crate::shape::{components, spans, sphere_roots, Crossing, Hit, Shape};
 use crate::vec3::Vec3;
 
 #[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
@@ -11,7 +13,7 @@ pub struct Sphere {
 
 impl Shape for Sphere {
     /// [guide](https://www.scratchapixel.com/lessons/3d-basic-rendering/minimal-ray-tracer-rendering-simple-shapes/ray-sphere-intersection.html)
-    fn reflect(&self, ray: &Ray) -> Option<(Ray, Vec3)> {
+    fn reflect(&self, ray: &Ray) -> Option<Hit> {
         let l = self.center - ray.start;
         let t_ca = l.dot(&ray.dir);
         if t_ca < 0. {
@@ -27,20 +29,78 @@ impl Shape for Sphere {
         let normal = (p1 - self.center).norm();
         let dir = (p1 - ray.start).reflect(&normal).norm();
 
-        Some((Ray { start: p1, dir }, normal))
+        let (dpdu, dpdv) = sphere_derivatives(&(p1 - self.center));
+        Some(Hit {
+            ray: Ray { start: p1, dir },
+            normal,
+            uv: sphere_uv(&normal),
+            dpdu,
+            dpdv,
+            ..Default::default()
+        })
     }
 
     fn center(&self) -> Vec3 {
         self.center
     }
+
+    fn area(&self) -> f32 {
+        4. * PI * self.radius * self.radius
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.extend(components(&[self.center]));
+        out.push(self.radius);
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<(Crossing, Crossing)> {
+        let crossings = sphere_roots(ray, &self.center, self.radius)
+            .into_iter()
+            .map(|t| {
+                let p = ray.with_param(t) - self.center;
+                let normal = p.norm();
+                let (dpdu, dpdv) = sphere_derivatives(&p);
+                Crossing {
+                    t,
+                    normal,
+                    uv: sphere_uv(&normal),
+                    dpdu,
+                    dpdv,
+                }
+            })
+            .collect();
+        spans(crossings)
+    }
+}
+
+/// Texture coordinates of a point on a unit sphere, with poles along z axis
+/// [ref](https://en.wikipedia.org/wiki/UV_mapping#Finding_UV_on_a_sphere)
+pub fn sphere_uv(p: &Vec3) -> (f32, f32) {
+    (
+        0.5 + p.y.atan2(p.x) / (2. * PI),
+        0.5 - p.z.clamp(-1., 1.).asin() / PI,
+    )
+}
+
+/// Derivatives of the point `p` relative to the sphere center by `sphere_uv` coordinates
+fn sphere_derivatives(p: &Vec3) -> (Vec3, Vec3) {
+    let rho = (p.x * p.x + p.y * p.y).sqrt();
+    if rho < 1e-6 {
+        return (Vec3::zero(), Vec3::zero());
+    }
+    (
+        Vec3::new(-p.y, p.x, 0.).mul_n(2. * PI),
+        Vec3::new(p.z * p.x / rho, p.z * p.y / rho, -rho).mul_n(PI),
+    )
 }
 
 #[cfg(test)]
 mod test {
     use std::f32::consts::PI;
 
+    use crate::math::approx_eq;
     use crate::ray::Ray;
-    use crate::shape::sphere::Sphere;
+    use crate::shape::sphere::{sphere_derivatives, sphere_uv, Sphere};
     use crate::shape::Shape;
     use crate::vec3::Vec3;
 
@@ -89,7 +149,7 @@ mod test {
 
         let ray = s.reflect(&r);
 
-        assert!(ray.unwrap().0.start.approx_eq(&Vec3::new(2., 1., 1.)))
+        assert!(ray.unwrap().ray.start.approx_eq(&Vec3::new(2., 1., 1.)))
     }
 
     #[test]
@@ -105,7 +165,7 @@ mod test {
 
         let ray = s.reflect(&r);
 
-        assert!(ray.unwrap().0.start.approx_eq(&Vec3::new(1., 0., 1.)));
+        assert!(ray.unwrap().ray.start.approx_eq(&Vec3::new(1., 0., 1.)));
     }
 
     #[test]
@@ -121,7 +181,7 @@ mod test {
 
         let ray = s.reflect(&r).unwrap();
 
-        assert!(ray.0.dir.approx_eq(&Vec3::new(1., 0., 0.)))
+        assert!(ray.ray.dir.approx_eq(&Vec3::new(1., 0., 0.)))
     }
 
     #[test]
@@ -137,7 +197,39 @@ mod test {
 
         let ray = s.reflect(&r).unwrap();
 
-        assert!(ray.0.dir.approx_eq(&Vec3::new(-1., 0., 0.)));
+        assert!(ray.ray.dir.approx_eq(&Vec3::new(-1., 0., 0.)));
+    }
+
+    #[test]
+    fn uv() {
+        let s = Sphere {
+            center: Vec3::new(2., 0., 1.),
+            radius: 1.0,
+        };
+        let r = Ray {
+            start: Vec3::new(0., 0., 1.),
+            dir: Vec3::new(1., 0., 0.),
+        };
+
+        let (u, v) = s.reflect(&r).unwrap().uv;
+
+        assert!(approx_eq(u, 1.) && approx_eq(v, 0.5));
+        assert_eq!(sphere_uv(&Vec3::new(0., 0., 1.)).1, 0.);
+        assert_eq!(sphere_uv(&Vec3::new(0., 0., -1.)).1, 1.);
+        assert!(approx_eq(sphere_uv(&Vec3::new(0., 1., 0.)).0, 0.75));
+    }
+
+    #[test]
+    fn derivatives() {
+        let p = Vec3::new(0.3, -0.5, 0.6).norm().mul_n(2.);
+        let (dpdu, dpdv) = sphere_derivatives(&p);
+        let d = 1e-3;
+        let uv = sphere_uv(&p.norm());
+        // step along the derivative changes the coordinate by the step
+        let shifted = |dp: Vec3| sphere_uv(&(p + dp.mul_n(d)).norm());
+        assert!(approx_eq((shifted(dpdu).0 - uv.0) / d, 1.));
+        assert!(approx_eq((shifted(dpdv).1 - uv.1) / d, 1.));
+        assert!(approx_eq((shifted(dpdu).1 - uv.1) / d, 0.));
     }
 
     #[test]
@@ -153,6 +245,6 @@ mod test {
 
         let ray = s.reflect(&r).unwrap();
 
-        assert!(ray.0.dir.approx_eq(&Vec3::new(0., 1., 0.)));
+        assert!(ray.ray.dir.approx_eq(&Vec3::new(0., 1., 0.)));
     }
 }
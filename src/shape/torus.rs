@@ -0,0 +1,158 @@
+use std::f32::consts::PI;
+
+use crate::math::solve_quartic;
+use crate::ray::Ray;
+use crate::shape::{
+    azimuth, closest_hit, components, spans, sphere_roots, Crossing, Frame, Hit, Shape,
+};
+use crate::vec3::Vec3;
+
+/// Torus around the `center`, lying in the plane perpendicular to the unit `axis`
+/// Texture coordinates are the angles around the axis and around the tube
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Torus {
+    pub center: Vec3,
+    pub axis: Vec3,
+    /// Distance from the center to the tube center
+    pub major_radius: f32,
+    /// Tube radius
+    pub minor_radius: f32,
+}
+
+impl Torus {
+    /// Crossings of the ray with the surface
+    /// [ref](https://www.cl.cam.ac.uk/teaching/1999/AGraphHCI/SMAG/node2.html)
+    fn crossings(&self, ray: &Ray) -> Vec<Crossing> {
+        let frame = Frame::new(self.center, self.axis);
+        let local = frame.local_ray(ray);
+        let (big_r, r) = (self.major_radius, self.minor_radius);
+        // quartic is poorly conditioned far from the torus, start from the bounding sphere
+        let bounds = sphere_roots(&local, &Vec3::zero(), big_r + r);
+        let (Some(&shift), Some(&last)) = (bounds.first(), bounds.last()) else {
+            return vec![];
+        };
+        if last < 0. {
+            return vec![];
+        }
+        let s = local.with_param(shift);
+        let d = local.dir;
+        let (sd, e) = (s.dot(&d) as f64, (s.dot(&s) - big_r * big_r - r * r) as f64);
+        let (dd, r2) = (d.dot(&d) as f64, (big_r * big_r) as f64 * 4.);
+        let roots = solve_quartic(
+            dd * dd,
+            4. * dd * sd,
+            2. * dd * e + 4. * sd * sd + r2 * (d.z * d.z) as f64,
+            4. * sd * e + 2. * r2 * (s.z * d.z) as f64,
+            e * e - r2 * (r * r - s.z * s.z) as f64,
+        );
+        let hits = roots.into_iter().map(|t| {
+            let t = t as f32 + shift;
+            let p = local.with_param(t);
+            let rho = (p.x * p.x + p.y * p.y).sqrt();
+            let (u, dpdu) = azimuth(&p);
+            let theta = p.z.atan2(rho - big_r);
+            let (sin_t, cos_t) = theta.sin_cos();
+            let (sin_p, cos_p) = (2. * PI * u).sin_cos();
+            Crossing {
+                t,
+                // from the tube center circle to the point
+                normal: p - Vec3::new(cos_p * big_r, sin_p * big_r, 0.),
+                uv: (u, theta.rem_euclid(2. * PI) / (2. * PI)),
+                dpdu,
+                dpdv: Vec3::new(-sin_t * cos_p, -sin_t * sin_p, cos_t).mul_n(2. * PI * r),
+            }
+        });
+        frame.crossings(hits)
+    }
+}
+
+impl Shape for Torus {
+    fn reflect(&self, ray: &Ray) -> Option<Hit> {
+        closest_hit(ray, self.crossings(ray))
+    }
+
+    fn center(&self) -> Vec3 {
+        self.center
+    }
+
+    fn area(&self) -> f32 {
+        4. * PI * PI * self.major_radius * self.minor_radius
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.extend(components(&[self.center, self.axis]));
+        out.extend([self.major_radius, self.minor_radius]);
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<(Crossing, Crossing)> {
+        spans(self.crossings(ray))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::math::approx_eq;
+    use crate::ray::Ray;
+    use crate::shape::torus::Torus;
+    use crate::shape::Shape;
+    use crate::vec3::Vec3;
+
+    fn torus() -> Torus {
+        Torus {
+            center: Vec3::new(0., 0., 0.),
+            axis: Vec3::new(0., 0., 1.),
+            major_radius: 2.,
+            minor_radius: 0.5,
+        }
+    }
+
+    #[test]
+    fn reflect_outer() {
+        let r = Ray {
+            start: Vec3::new(-10., 0., 0.),
+            dir: Vec3::new(1., 0., 0.),
+        };
+        let hit = torus().reflect(&r).unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(-2.5, 0., 0.)), "{hit:?}");
+        assert!(hit.normal.approx_eq(&Vec3::new(-1., 0., 0.)));
+        assert!(approx_eq(hit.uv.0, 0.5) && approx_eq(hit.uv.1, 0.));
+    }
+
+    #[test]
+    fn reflect_top() {
+        let r = Ray {
+            start: Vec3::new(0., 2., 100.),
+            dir: Vec3::new(0., 0., -1.),
+        };
+        let hit = torus().reflect(&r).unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(0., 2., 0.5)), "{hit:?}");
+        assert!(hit.normal.approx_eq(&Vec3::new(0., 0., 1.)));
+        assert!(approx_eq(hit.uv.1, 0.25));
+    }
+
+    #[test]
+    fn miss() {
+        // through the hole
+        let r = Ray {
+            start: Vec3::new(0., 0., 10.),
+            dir: Vec3::new(0., 0., -1.),
+        };
+        assert!(torus().reflect(&r).is_none());
+        let r = Ray {
+            start: Vec3::new(-10., 0., 0.6),
+            dir: Vec3::new(1., 0., 0.),
+        };
+        assert!(torus().reflect(&r).is_none());
+    }
+
+    #[test]
+    fn reflect_inner() {
+        let r = Ray {
+            start: Vec3::new(0., 0., 0.),
+            dir: Vec3::new(0., 1., 0.),
+        };
+        let hit = torus().reflect(&r).unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(0., 1.5, 0.)), "{hit:?}");
+        assert!(hit.normal.approx_eq(&Vec3::new(0., -1., 0.)));
+    }
+}
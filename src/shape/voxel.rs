@@ -0,0 +1,461 @@
+use std::fs::read;
+use std::io::{Error, ErrorKind};
+
+use crate::color::Color;
+use crate::ray::Ray;
+use crate::shape::cuboid::Cuboid;
+use crate::shape::{components, Hit, Shape, HIT_EPSILON};
+use crate::texture::palette::Palette;
+use crate::vec3::Vec3;
+
+/// Dense grid of cubic voxels, every voxel holds its material index, 0 is empty
+/// Material index is reported by hits, voxel faces have their own texture coordinates
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoxelGrid {
+    pub size: [usize; 3],
+    /// Voxel material indices, x changes fastest, then y, then z
+    pub voxels: Vec<u8>,
+    /// Corner of the voxel (0, 0, 0)
+    pub min: Vec3,
+    /// Voxel edge length
+    pub voxel_size: f32,
+}
+
+impl VoxelGrid {
+    pub fn new(size: [usize; 3], min: Vec3, voxel_size: f32) -> VoxelGrid {
+        assert!(
+            size.iter().all(|s| *s > 0),
+            "voxel grid needs non-zero size"
+        );
+        VoxelGrid {
+            size,
+            voxels: vec![0; size[0] * size[1] * size[2]],
+            min,
+            voxel_size,
+        }
+    }
+
+    fn index(&self, v: [usize; 3]) -> usize {
+        (v[2] * self.size[1] + v[1]) * self.size[0] + v[0]
+    }
+
+    pub fn get(&self, v: [usize; 3]) -> u8 {
+        self.voxels[self.index(v)]
+    }
+
+    pub fn set(&mut self, v: [usize; 3], material: u8) {
+        let i = self.index(v);
+        self.voxels[i] = material;
+    }
+
+    /// Material of the voxel, 0 outside of the grid
+    fn get_signed(&self, v: [isize; 3]) -> u8 {
+        if (0..3).any(|a| v[a] < 0 || v[a] as usize >= self.size[a]) {
+            return 0;
+        }
+        self.get(v.map(|c| c as usize))
+    }
+
+    fn bounds(&self) -> Cuboid {
+        let s = self.size.map(|s| s as f32 * self.voxel_size);
+        Cuboid {
+            min: self.min,
+            max: self.min + Vec3::new(s[0], s[1], s[2]),
+        }
+    }
+
+    /// Hit of the voxel face perpendicular to the axis `a`
+    fn face_hit(&self, ray: &Ray, t: f32, a: usize, material: u8) -> Hit {
+        let mut n = [0.; 3];
+        n[a] = -axis(&ray.dir, a).signum();
+        let normal = Vec3::new(n[0], n[1], n[2]);
+        let p = ray.with_param(t);
+        let rel = (p - self.min).mul_n(1. / self.voxel_size);
+        let (b, c) = ((a + 1) % 3, (a + 2) % 3);
+        let unit = |i: usize| {
+            let mut v = [0.; 3];
+            v[i] = self.voxel_size;
+            Vec3::new(v[0], v[1], v[2])
+        };
+        Hit {
+            ray: Ray {
+                start: p,
+                dir: ray.dir.reflect(&normal).norm(),
+            },
+            normal,
+            uv: (axis(&rel, b).rem_euclid(1.), axis(&rel, c).rem_euclid(1.)),
+            dpdu: unit(b),
+            dpdv: unit(c),
+            material: material as usize,
+            ..Default::default()
+        }
+    }
+}
+
+impl Shape for VoxelGrid {
+    /// Walks the voxels along the ray with 3D DDA
+    /// [ref](http://www.cse.yorku.ca/~amana/research/grid.pdf)
+    fn reflect(&self, ray: &Ray) -> Option<Hit> {
+        let ((t_enter, enter_axis), (t_exit, _)) = self.bounds().slabs(ray)?;
+        let t0 = t_enter.max(0.);
+        // voxel is found slightly along the ray, off the face the ray is reflected from
+        let p = (ray.with_param(t0 + HIT_EPSILON) - self.min).mul_n(1. / self.voxel_size);
+        let mut v =
+            [0, 1, 2].map(|a| (axis(&p, a).floor() as isize).clamp(0, self.size[a] as isize - 1));
+        let dir = [ray.dir.x, ray.dir.y, ray.dir.z];
+        let step = dir.map(|d| if d > 0. { 1 } else { -1 });
+        let mut next = [0, 1, 2].map(|a| {
+            if dir[a] == 0. {
+                return f32::MAX;
+            }
+            let boundary = (v[a] + if dir[a] > 0. { 1 } else { 0 }) as f32 * self.voxel_size;
+            (boundary + axis(&self.min, a) - axis(&ray.start, a)) / dir[a]
+        });
+        let delta = dir.map(|d| {
+            if d == 0. {
+                f32::MAX
+            } else {
+                self.voxel_size / d.abs()
+            }
+        });
+        let start = self.get_signed(v);
+        if start != 0 && t_enter >= HIT_EPSILON {
+            return Some(self.face_hit(ray, t_enter, enter_axis, start));
+        }
+        loop {
+            let a = (0..3).min_by(|i, j| next[*i].total_cmp(&next[*j])).unwrap();
+            let t = next[a];
+            if t > t_exit {
+                return None;
+            }
+            v[a] += step[a];
+            if v[a] < 0 || v[a] >= self.size[a] as isize {
+                return None;
+            }
+            next[a] += delta[a];
+            let material = self.get_signed(v);
+            if material != 0 {
+                return Some(self.face_hit(ray, t, a, material));
+            }
+        }
+    }
+
+    fn center(&self) -> Vec3 {
+        self.bounds().center()
+    }
+
+    /// Area of voxel faces exposed to empty space
+    fn area(&self) -> f32 {
+        let mut faces = 0;
+        for z in 0..self.size[2] {
+            for y in 0..self.size[1] {
+                for x in 0..self.size[0] {
+                    if self.get([x, y, z]) == 0 {
+                        continue;
+                    }
+                    let v = [x, y, z].map(|c| c as isize);
+                    for a in 0..3 {
+                        for d in [-1, 1] {
+                            let mut n = v;
+                            n[a] += d;
+                            if self.get_signed(n) == 0 {
+                                faces += 1;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        faces as f32 * self.voxel_size * self.voxel_size
+    }
+
+    fn fingerprint(&self, out: &mut Vec<f32>) {
+        out.extend(self.size.map(|s| s as f32));
+        out.extend(components(&[self.min]));
+        out.push(self.voxel_size);
+        out.extend(self.voxels.iter().map(|v| *v as f32));
+    }
+}
+
+fn axis(v: &Vec3, a: usize) -> f32 {
+    match a {
+        0 => v.x,
+        1 => v.y,
+        _ => v.z,
+    }
+}
+
+/// Voxel model along with its palette, indexed by voxel material
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoxModel {
+    pub grid: VoxelGrid,
+    pub palette: Palette,
+}
+
+/// MagicaVoxel default palette as 0xAABBGGRR, used by files without the RGBA chunk
+const DEFAULT_PALETTE: [u32; 256] = [
+    0x00000000, 0xffffffff, 0xffccffff, 0xff99ffff, 0xff66ffff, 0xff33ffff, 0xff00ffff, 0xffffccff,
+    0xffccccff, 0xff99ccff, 0xff66ccff, 0xff33ccff, 0xff00ccff, 0xffff99ff, 0xffcc99ff, 0xff9999ff,
+    0xff6699ff, 0xff3399ff, 0xff0099ff, 0xffff66ff, 0xffcc66ff, 0xff9966ff, 0xff6666ff, 0xff3366ff,
+    0xff0066ff, 0xffff33ff, 0xffcc33ff, 0xff9933ff, 0xff6633ff, 0xff3333ff, 0xff0033ff, 0xffff00ff,
+    0xffcc00ff, 0xff9900ff, 0xff6600ff, 0xff3300ff, 0xff0000ff, 0xffffffcc, 0xffccffcc, 0xff99ffcc,
+    0xff66ffcc, 0xff33ffcc, 0xff00ffcc, 0xffffcccc, 0xffcccccc, 0xff99cccc, 0xff66cccc, 0xff33cccc,
+    0xff00cccc, 0xffff99cc, 0xffcc99cc, 0xff9999cc, 0xff6699cc, 0xff3399cc, 0xff0099cc, 0xffff66cc,
+    0xffcc66cc, 0xff9966cc, 0xff6666cc, 0xff3366cc, 0xff0066cc, 0xffff33cc, 0xffcc33cc, 0xff9933cc,
+    0xff6633cc, 0xff3333cc, 0xff0033cc, 0xffff00cc, 0xffcc00cc, 0xff9900cc, 0xff6600cc, 0xff3300cc,
+    0xff0000cc, 0xffffff99, 0xffccff99, 0xff99ff99, 0xff66ff99, 0xff33ff99, 0xff00ff99, 0xffffcc99,
+    0xffcccc99, 0xff99cc99, 0xff66cc99, 0xff33cc99, 0xff00cc99, 0xffff9999, 0xffcc9999, 0xff999999,
+    0xff669999, 0xff339999, 0xff009999, 0xffff6699, 0xffcc6699, 0xff996699, 0xff666699, 0xff336699,
+    0xff006699, 0xffff3399, 0xffcc3399, 0xff993399, 0xff663399, 0xff333399, 0xff003399, 0xffff0099,
+    0xffcc0099, 0xff990099, 0xff660099, 0xff330099, 0xff000099, 0xffffff66, 0xffccff66, 0xff99ff66,
+    0xff66ff66, 0xff33ff66, 0xff00ff66, 0xffffcc66, 0xffcccc66, 0xff99cc66, 0xff66cc66, 0xff33cc66,
+    0xff00cc66, 0xffff9966, 0xffcc9966, 0xff999966, 0xff669966, 0xff339966, 0xff009966, 0xffff6666,
+    0xffcc6666, 0xff996666, 0xff666666, 0xff336666, 0xff006666, 0xffff3366, 0xffcc3366, 0xff993366,
+    0xff663366, 0xff333366, 0xff003366, 0xffff0066, 0xffcc0066, 0xff990066, 0xff660066, 0xff330066,
+    0xff000066, 0xffffff33, 0xffccff33, 0xff99ff33, 0xff66ff33, 0xff33ff33, 0xff00ff33, 0xffffcc33,
+    0xffcccc33, 0xff99cc33, 0xff66cc33, 0xff33cc33, 0xff00cc33, 0xffff9933, 0xffcc9933, 0xff999933,
+    0xff669933, 0xff339933, 0xff009933, 0xffff6633, 0xffcc6633, 0xff996633, 0xff666633, 0xff336633,
+    0xff006633, 0xffff3333, 0xffcc3333, 0xff993333, 0xff663333, 0xff333333, 0xff003333, 0xffff0033,
+    0xffcc0033, 0xff990033, 0xff660033, 0xff330033, 0xff000033, 0xffffff00, 0xffccff00, 0xff99ff00,
+    0xff66ff00, 0xff33ff00, 0xff00ff00, 0xffffcc00, 0xffcccc00, 0xff99cc00, 0xff66cc00, 0xff33cc00,
+    0xff00cc00, 0xffff9900, 0xffcc9900, 0xff999900, 0xff669900, 0xff339900, 0xff009900, 0xffff6600,
+    0xffcc6600, 0xff996600, 0xff666600, 0xff336600, 0xff006600, 0xffff3300, 0xffcc3300, 0xff993300,
+    0xff663300, 0xff333300, 0xff003300, 0xffff0000, 0xffcc0000, 0xff990000, 0xff660000, 0xff330000,
+    0xff0000ee, 0xff0000dd, 0xff0000bb, 0xff0000aa, 0xff000088, 0xff000077, 0xff000055, 0xff000044,
+    0xff000022, 0xff000011, 0xff00ee00, 0xff00dd00, 0xff00bb00, 0xff00aa00, 0xff008800, 0xff007700,
+    0xff005500, 0xff004400, 0xff002200, 0xff001100, 0xffee0000, 0xffdd0000, 0xffbb0000, 0xffaa0000,
+    0xff880000, 0xff770000, 0xff550000, 0xff440000, 0xff220000, 0xff110000, 0xffeeeeee, 0xffdddddd,
+    0xffbbbbbb, 0xffaaaaaa, 0xff888888, 0xff777777, 0xff555555, 0xff444444, 0xff222222, 0xff111111,
+];
+
+/// Load the first model of the [MagicaVoxel](https://github.com/ephtracy/voxel-model) file, with
+/// unit voxels and the grid corner at the origin
+/// Files without the palette get the MagicaVoxel default palette
+pub fn load_vox(path: &str) -> std::io::Result<VoxModel> {
+    parse_vox(&read(path)?)
+}
+
+pub fn parse_vox(data: &[u8]) -> std::io::Result<VoxModel> {
+    let invalid = |msg: &str| Error::new(ErrorKind::InvalidData, msg);
+    let int = |pos: usize| -> std::io::Result<usize> {
+        data.get(pos..pos + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+            .ok_or_else(|| invalid("unexpected end of VOX file"))
+    };
+    if data.get(..4) != Some(b"VOX ") {
+        return Err(invalid("not a VOX file"));
+    }
+    let (mut size, mut voxels, mut colors) = (None, None, None);
+    // chunks of MAIN are read flat, header of MAIN itself has no content
+    let mut pos = 8;
+    while pos + 12 <= data.len() {
+        let id = &data[pos..pos + 4];
+        let content = int(pos + 4)?;
+        let body = pos + 12;
+        let end = body + content;
+        if end > data.len() {
+            return Err(invalid("unexpected end of VOX file"));
+        }
+        match id {
+            b"MAIN" => {
+                pos = body;
+                continue;
+            }
+            b"SIZE" if size.is_none() => {
+                let dims = [int(body)?, int(body + 4)?, int(body + 8)?];
+                // voxel coordinates are bytes, larger models are split into several ones
+                if dims.iter().any(|d| *d > 256) {
+                    return Err(invalid("VOX model too large"));
+                }
+                if dims.contains(&0) {
+                    return Err(invalid("empty VOX model"));
+                }
+                size = Some(dims);
+            }
+            b"XYZI" if voxels.is_none() => {
+                let n = int(body)?;
+                let bytes = data
+                    .get(body + 4..body + 4 + 4 * n)
+                    .ok_or_else(|| invalid("unexpected end of VOX file"))?;
+                voxels = Some(
+                    bytes
+                        .chunks(4)
+                        .map(|v| [v[0], v[1], v[2], v[3]])
+                        .collect::<Vec<_>>(),
+                );
+            }
+            b"RGBA" => {
+                let bytes = data
+                    .get(body..body + 1024)
+                    .ok_or_else(|| invalid("unexpected end of VOX file"))?;
+                // color i of the chunk is for the voxel material i + 1
+                let mut palette = vec![Color::BLACK];
+                palette.extend(bytes.chunks(4).take(255).map(|c| {
+                    Color::rgb(c[0] as f32 / 255., c[1] as f32 / 255., c[2] as f32 / 255.)
+                }));
+                colors = Some(palette);
+            }
+            _ => {}
+        }
+        // children of the chunk follow its content
+        pos = end;
+    }
+    let size = size.ok_or_else(|| invalid("no SIZE chunk in VOX file"))?;
+    let mut grid = VoxelGrid::new(size, Vec3::zero(), 1.);
+    for [x, y, z, i] in voxels.ok_or_else(|| invalid("no XYZI chunk in VOX file"))? {
+        let v = [x, y, z].map(|c| c as usize);
+        if (0..3).any(|a| v[a] >= size[a]) {
+            return Err(invalid("voxel outside of the VOX model"));
+        }
+        grid.set(v, i);
+    }
+    let colors = colors.unwrap_or_else(|| {
+        DEFAULT_PALETTE
+            .iter()
+            .map(|c| c.to_le_bytes())
+            .map(|c| Color::rgb(c[0] as f32 / 255., c[1] as f32 / 255., c[2] as f32 / 255.))
+            .collect()
+    });
+    Ok(VoxModel {
+        grid,
+        palette: Palette { colors },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::color::Color;
+    use crate::math::approx_eq;
+    use crate::ray::Ray;
+    use crate::shape::voxel::{parse_vox, VoxelGrid};
+    use crate::shape::Shape;
+    use crate::vec3::Vec3;
+
+    /// L-shaped 3 voxels in the xy plane, with materials 1, 2, 3
+    fn grid() -> VoxelGrid {
+        let mut g = VoxelGrid::new([4, 4, 2], Vec3::zero(), 0.5);
+        g.set([0, 0, 0], 1);
+        g.set([1, 0, 0], 2);
+        g.set([0, 1, 0], 3);
+        g
+    }
+
+    fn ray(start: Vec3, dir: Vec3) -> Ray {
+        Ray {
+            start,
+            dir: dir.norm(),
+        }
+    }
+
+    #[test]
+    fn reflect() {
+        let g = grid();
+        let hit = g
+            .reflect(&ray(Vec3::new(0.75, 0.25, 5.), Vec3::new(0., 0., -1.)))
+            .unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(0.75, 0.25, 0.5)));
+        assert!(hit.normal.approx_eq(&Vec3::new(0., 0., 1.)));
+        assert_eq!(hit.material, 2);
+        assert!(approx_eq(hit.uv.0, 0.5) && approx_eq(hit.uv.1, 0.5));
+
+        // along the row from outside, entering through the grid boundary
+        let hit = g
+            .reflect(&ray(Vec3::new(-3., 0.25, 0.25), Vec3::new(1., 0., 0.)))
+            .unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(0., 0.25, 0.25)));
+        assert_eq!(hit.material, 1);
+
+        // from the other side, through empty voxels
+        let hit = g
+            .reflect(&ray(Vec3::new(5., 0.75, 0.25), Vec3::new(-1., 0., 0.)))
+            .unwrap();
+        assert!(hit.ray.start.approx_eq(&Vec3::new(0.5, 0.75, 0.25)));
+        assert!(hit.normal.approx_eq(&Vec3::new(1., 0., 0.)));
+        assert_eq!(hit.material, 3);
+    }
+
+    #[test]
+    fn reflected_ray() {
+        let g = grid();
+        let hit = g
+            .reflect(&ray(Vec3::new(2., 0.25, 1.75), Vec3::new(-1., 0., -1.)))
+            .unwrap();
+        assert_eq!(hit.material, 2);
+        assert!(hit.ray.start.approx_eq(&Vec3::new(0.75, 0.25, 0.5)));
+        // reflected off the top face, away from the grid
+        assert!(g.reflect(&hit.ray).is_none());
+    }
+
+    #[test]
+    fn miss() {
+        let g = grid();
+        assert!(g
+            .reflect(&ray(Vec3::new(1.25, 1.25, 5.), Vec3::new(0., 0., -1.)))
+            .is_none());
+        assert!(g
+            .reflect(&ray(Vec3::new(-1., -1., 0.25), Vec3::new(-1., 0., 0.)))
+            .is_none());
+        assert!(approx_eq(g.area(), 14. * 0.25));
+    }
+
+    #[test]
+    fn vox() {
+        let chunk = |id: &[u8], content: Vec<u8>, children: Vec<u8>| {
+            let mut c = id.to_vec();
+            c.extend((content.len() as u32).to_le_bytes());
+            c.extend((children.len() as u32).to_le_bytes());
+            c.extend(content);
+            c.extend(children);
+            c
+        };
+        let size = chunk(
+            b"SIZE",
+            [2u32, 1, 3].iter().flat_map(|v| v.to_le_bytes()).collect(),
+            vec![],
+        );
+        let mut xyzi = 2u32.to_le_bytes().to_vec();
+        xyzi.extend([0, 0, 0, 1, 1, 0, 2, 3]);
+        let xyzi = chunk(b"XYZI", xyzi, vec![]);
+        let mut rgba = vec![0; 1024];
+        rgba[..4].copy_from_slice(&[255, 0, 0, 255]);
+        rgba[8..12].copy_from_slice(&[0, 0, 255, 255]);
+        let rgba = chunk(b"RGBA", rgba, vec![]);
+        let vox = |chunks: Vec<Vec<u8>>| {
+            let mut data = b"VOX ".to_vec();
+            data.extend(150u32.to_le_bytes());
+            data.extend(chunk(b"MAIN", vec![], chunks.concat()));
+            data
+        };
+
+        let model = parse_vox(&vox(vec![size.clone(), xyzi.clone(), rgba])).unwrap();
+        assert_eq!(model.grid.size, [2, 1, 3]);
+        assert_eq!(model.grid.get([0, 0, 0]), 1);
+        assert_eq!(model.grid.get([1, 0, 2]), 3);
+        assert_eq!(model.grid.get([1, 0, 0]), 0);
+        assert_eq!(model.palette.colors[1], Color::RED);
+        assert_eq!(model.palette.colors[3], Color::BLUE);
+        assert!(parse_vox(b"VOX \x96\x00\x00\x00").is_err());
+
+        let model = parse_vox(&vox(vec![size, xyzi.clone()])).unwrap();
+        assert_eq!(model.palette.colors[0], Color::BLACK);
+        assert_eq!(model.palette.colors[1], Color::WHITE);
+        assert_eq!(model.palette.colors[36], Color::RED);
+        assert_eq!(model.palette.colors.len(), 256);
+
+        let huge = chunk(
+            b"SIZE",
+            [2u32, 1, 300]
+                .iter()
+                .flat_map(|v| v.to_le_bytes())
+                .collect(),
+            vec![],
+        );
+        assert!(parse_vox(&vox(vec![huge, xyzi.clone()])).is_err());
+        let empty = chunk(
+            b"SIZE",
+            [2u32, 2, 0].iter().flat_map(|v| v.to_le_bytes()).collect(),
+            vec![],
+        );
+        assert!(parse_vox(&vox(vec![empty, xyzi])).is_err());
+    }
+}
@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use crate::color::Color;
+use crate::shape::Hit;
+use crate::texture::Texture;
+
+/// Gray color of the linear function `offset + scale * value` of the texture color channel,
+/// for textures packing several material properties into their channels
+#[derive(Debug, Clone)]
+pub struct Channel {
+    pub texture: Arc<dyn Texture>,
+    /// Channel index, 0 for red, 1 for green and 2 for blue
+    pub channel: usize,
+    pub offset: f32,
+    pub scale: f32,
+}
+
+impl Channel {
+    pub fn new(texture: Arc<dyn Texture>, channel: usize) -> Channel {
+        Channel {
+            texture,
+            channel,
+            offset: 0.,
+            scale: 1.,
+        }
+    }
+}
+
+impl Texture for Channel {
+    fn sample(&self, hit: &Hit) -> Color {
+        let c = self.texture.sample(hit);
+        let v = [c.r, c.g, c.b][self.channel.min(2)];
+        Color::mono(self.offset + self.scale * v)
+    }
+}
@@ -0,0 +1,158 @@
+use std::fmt::{Debug, Formatter};
+use std::io;
+
+use crate::color::Color;
+use crate::image::Image;
+use crate::shape::Hit;
+use crate::texture::Texture;
+
+/// How texture coordinates outside of [0, 1] range are mapped onto the image
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Wrap {
+    /// Tile the image
+    #[default]
+    Repeat,
+    /// Tile the image, flipping every other copy
+    Mirror,
+    /// Extend image edges
+    Clamp,
+}
+
+impl Wrap {
+    /// Map a texel coordinate into the [0, size) range
+    fn apply(&self, i: isize, size: usize) -> usize {
+        let n = size as isize;
+        match self {
+            Wrap::Repeat => i.rem_euclid(n) as usize,
+            Wrap::Mirror => {
+                let m = i.rem_euclid(2 * n);
+                (if m < n { m } else { 2 * n - 1 - m }) as usize
+            }
+            Wrap::Clamp => i.clamp(0, n - 1) as usize,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Filter {
+    Nearest,
+    #[default]
+    Bilinear,
+}
+
+/// Texture mapping the image onto the surface by its texture coordinates
+/// Image top left corner is at (0, 0) and bottom right corner is at (1, 1)
+#[derive(Clone, PartialEq, Default)]
+pub struct ImageTexture {
+    pub image: Image,
+    pub wrap: Wrap,
+    pub filter: Filter,
+}
+
+impl ImageTexture {
+    pub fn new(image: Image) -> ImageTexture {
+        ImageTexture {
+            image,
+            ..Default::default()
+        }
+    }
+
+    /// Load PPM or PNG image texture
+    pub fn load(path: &str) -> io::Result<ImageTexture> {
+        Ok(ImageTexture::new(Image::load(path)?))
+    }
+
+    pub fn lookup(&self, u: f32, v: f32) -> Color {
+        let (w, h) = (self.image.width, self.image.height);
+        if w == 0 || h == 0 {
+            return Color::BLACK;
+        }
+        let texel =
+            |x: isize, y: isize| self.image.get(self.wrap.apply(x, w), self.wrap.apply(y, h));
+        let x = u * w as f32;
+        let y = v * h as f32;
+        match self.filter {
+            Filter::Nearest => texel(x.floor() as isize, y.floor() as isize),
+            Filter::Bilinear => {
+                // texel centers are at half-integer coordinates
+                let (x, y) = (x - 0.5, y - 0.5);
+                let (x0, y0) = (x.floor(), y.floor());
+                let (fx, fy) = (x - x0, y - y0);
+                let (x0, y0) = (x0 as isize, y0 as isize);
+                let top = texel(x0, y0).lerp(&texel(x0 + 1, y0), fx);
+                let bottom = texel(x0, y0 + 1).lerp(&texel(x0 + 1, y0 + 1), fx);
+                top.lerp(&bottom, fy)
+            }
+        }
+    }
+}
+
+impl Texture for ImageTexture {
+    fn sample(&self, hit: &Hit) -> Color {
+        self.lookup(hit.uv.0, hit.uv.1)
+    }
+}
+
+/// Pixels are left out, they would flood scene dumps
+impl Debug for ImageTexture {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ImageTexture")
+            .field("width", &self.image.width)
+            .field("height", &self.image.height)
+            .field("wrap", &self.wrap)
+            .field("filter", &self.filter)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::color::Color;
+    use crate::image::Image;
+    use crate::math::approx_eq;
+    use crate::texture::image::{Filter, ImageTexture, Wrap};
+
+    fn texture() -> ImageTexture {
+        let mut image = Image::new(2, 2);
+        image.set(0, 0, Color::BLACK);
+        image.set(1, 0, Color::WHITE);
+        image.set(0, 1, Color::RED);
+        image.set(1, 1, Color::BLUE);
+        ImageTexture::new(image)
+    }
+
+    #[test]
+    fn texel_centers() {
+        let t = texture();
+        assert_eq!(t.lookup(0.25, 0.25), Color::BLACK);
+        assert_eq!(t.lookup(0.75, 0.25), Color::WHITE);
+        assert_eq!(t.lookup(0.25, 0.75), Color::RED);
+        assert_eq!(t.lookup(0.75, 0.75), Color::BLUE);
+    }
+
+    #[test]
+    fn bilinear() {
+        let t = ImageTexture {
+            wrap: Wrap::Clamp,
+            ..texture()
+        };
+        let c = t.lookup(0.5, 0.25);
+        assert!(approx_eq(c.r, 0.5) && approx_eq(c.g, 0.5));
+        let c = t.lookup(0.5, 0.5);
+        assert!(approx_eq(c.r, 0.5) && approx_eq(c.g, 0.25) && approx_eq(c.b, 0.5));
+    }
+
+    #[test]
+    fn wrap() {
+        let nearest = |wrap| ImageTexture {
+            wrap,
+            filter: Filter::Nearest,
+            ..texture()
+        };
+        assert_eq!(nearest(Wrap::Repeat).lookup(1.25, 0.25), Color::BLACK);
+        assert_eq!(nearest(Wrap::Mirror).lookup(1.25, 0.25), Color::WHITE);
+        assert_eq!(nearest(Wrap::Clamp).lookup(1.25, 0.25), Color::WHITE);
+        assert_eq!(nearest(Wrap::Repeat).lookup(-0.25, 0.25), Color::WHITE);
+        assert_eq!(nearest(Wrap::Clamp).lookup(-0.25, 0.25), Color::BLACK);
+    }
+}
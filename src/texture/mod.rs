@@ -0,0 +1,24 @@
+use std::fmt::Debug;
+
+use crate::color::Color;
+use crate::shape::Hit;
+
+pub mod channel;
+pub mod image;
+pub mod multiply;
+pub mod noise;
+pub mod palette;
+pub mod procedural;
+pub mod vertex_color;
+
+pub trait Texture: Debug + Send + Sync {
+    /// Texture color at the surface point
+    fn sample(&self, hit: &Hit) -> Color;
+}
+
+/// Constant color texture
+impl Texture for Color {
+    fn sample(&self, _: &Hit) -> Color {
+        *self
+    }
+}
@@ -0,0 +1,18 @@
+use std::sync::Arc;
+
+use crate::color::Color;
+use crate::shape::Hit;
+use crate::texture::Texture;
+
+/// Product of the two texture colors, e.g. an image tinted by a constant color
+#[derive(Debug, Clone)]
+pub struct Multiply {
+    pub a: Arc<dyn Texture>,
+    pub b: Arc<dyn Texture>,
+}
+
+impl Texture for Multiply {
+    fn sample(&self, hit: &Hit) -> Color {
+        self.a.sample(hit) * self.b.sample(hit)
+    }
+}
@@ -0,0 +1,207 @@
+use crate::vec3::Vec3;
+
+/// Hash of the integer lattice point, used in place of a permutation table
+fn hash(x: i32, y: i32, z: i32) -> u32 {
+    let mut h = (x as u32).wrapping_mul(0x8da6_b343)
+        ^ (y as u32).wrapping_mul(0xd816_3841)
+        ^ (z as u32).wrapping_mul(0xcb1a_b31f);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0x5bd1_e995);
+    h ^ (h >> 15)
+}
+
+/// Uniform value in [0, 1) derived from the hash
+fn unit(h: u32) -> f32 {
+    (h >> 8) as f32 / (1 << 24) as f32
+}
+
+/// Dot product of the offset with one of 12 cube edge gradients
+fn grad(h: u32, x: f32, y: f32, z: f32) -> f32 {
+    let h = h & 15;
+    let u = if h < 8 { x } else { y };
+    let v = match h {
+        0..=3 => y,
+        12 | 14 => x,
+        _ => z,
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6. - 15.) + 10.)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// [Improved Perlin noise](https://mrl.cs.nyu.edu/~perlin/noise/) in [-1, 1] range
+/// Zero at every integer lattice point
+pub fn perlin(p: Vec3) -> f32 {
+    let (xf, yf, zf) = (p.x.floor(), p.y.floor(), p.z.floor());
+    let (xi, yi, zi) = (xf as i32, yf as i32, zf as i32);
+    let (x, y, z) = (p.x - xf, p.y - yf, p.z - zf);
+    let (u, v, w) = (fade(x), fade(y), fade(z));
+    let g = |dx: i32, dy: i32, dz: i32| {
+        grad(
+            hash(xi + dx, yi + dy, zi + dz),
+            x - dx as f32,
+            y - dy as f32,
+            z - dz as f32,
+        )
+    };
+    lerp(
+        lerp(
+            lerp(g(0, 0, 0), g(1, 0, 0), u),
+            lerp(g(0, 1, 0), g(1, 1, 0), u),
+            v,
+        ),
+        lerp(
+            lerp(g(0, 0, 1), g(1, 0, 1), u),
+            lerp(g(0, 1, 1), g(1, 1, 1), u),
+            v,
+        ),
+        w,
+    )
+    .clamp(-1., 1.)
+}
+
+/// Fractal noise parameters
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Octaves {
+    pub count: usize,
+    /// Frequency multiplier of every next octave
+    pub lacunarity: f32,
+    /// Amplitude multiplier of every next octave
+    pub gain: f32,
+}
+
+impl Default for Octaves {
+    fn default() -> Self {
+        Octaves {
+            count: 5,
+            lacunarity: 2.,
+            gain: 0.5,
+        }
+    }
+}
+
+impl Octaves {
+    /// Weighted sum of `f` over octaves, normalized by the total amplitude
+    fn sum(&self, p: Vec3, f: impl Fn(Vec3) -> f32) -> f32 {
+        let (mut sum, mut total, mut amp, mut freq) = (0., 0., 1., 1.);
+        for _ in 0..self.count.max(1) {
+            sum += amp * f(p.mul_n(freq));
+            total += amp;
+            amp *= self.gain;
+            freq *= self.lacunarity;
+        }
+        sum / total
+    }
+}
+
+/// Fractal Brownian motion, sum of Perlin noise octaves in [-1, 1] range
+pub fn fbm(p: Vec3, octaves: &Octaves) -> f32 {
+    octaves.sum(p, perlin)
+}
+
+/// Sum of absolute Perlin noise octaves in [0, 1] range
+pub fn turbulence(p: Vec3, octaves: &Octaves) -> f32 {
+    octaves.sum(p, |p| perlin(p).abs())
+}
+
+/// Result of the cellular noise lookup
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Cell {
+    /// Distance to the closest feature point
+    pub f1: f32,
+    /// Distance to the second closest feature point
+    pub f2: f32,
+    /// Random value in [0, 1) identifying the closest feature point cell
+    pub id: f32,
+}
+
+/// [Worley noise](https://en.wikipedia.org/wiki/Worley_noise) with a single feature point in
+/// every lattice cell
+pub fn worley(p: Vec3) -> Cell {
+    let (xi, yi, zi) = (p.x.floor() as i32, p.y.floor() as i32, p.z.floor() as i32);
+    let mut cell = Cell {
+        f1: f32::MAX,
+        f2: f32::MAX,
+        id: 0.,
+    };
+    for dz in -1..=1 {
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let (x, y, z) = (xi + dx, yi + dy, zi + dz);
+                let h = hash(x, y, z);
+                let feature = Vec3::new(
+                    x as f32 + unit(h),
+                    y as f32 + unit(hash(h as i32, 1, 0)),
+                    z as f32 + unit(hash(h as i32, 0, 1)),
+                );
+                let d = p.dist(&feature);
+                if d < cell.f1 {
+                    cell.f2 = cell.f1;
+                    cell.f1 = d;
+                    cell.id = unit(hash(h as i32, 1, 1));
+                } else if d < cell.f2 {
+                    cell.f2 = d;
+                }
+            }
+        }
+    }
+    cell
+}
+
+#[cfg(test)]
+mod test {
+    use crate::texture::noise::{fbm, perlin, turbulence, worley, Octaves};
+    use crate::vec3::Vec3;
+
+    fn points() -> impl Iterator<Item = Vec3> {
+        (0..1000).map(|i| {
+            let i = i as f32;
+            Vec3::new(i * 0.137 - 50., i * 0.071 + 3.3, -i * 0.053)
+        })
+    }
+
+    #[test]
+    fn perlin_lattice() {
+        assert_eq!(perlin(Vec3::new(3., -2., 7.)), 0.);
+        assert_eq!(perlin(Vec3::zero()), 0.);
+        assert_ne!(perlin(Vec3::new(3.5, -2.3, 7.7)), 0.);
+    }
+
+    #[test]
+    fn perlin_continuous() {
+        for p in points() {
+            let d = perlin(p) - perlin(p + Vec3::diag(1e-3));
+            assert!(d.abs() < 0.01, "{p:?}");
+        }
+    }
+
+    #[test]
+    fn ranges() {
+        let o = Octaves::default();
+        let mut spread = (f32::MAX, f32::MIN);
+        for p in points() {
+            let n = perlin(p);
+            spread = (spread.0.min(n), spread.1.max(n));
+            assert!((-1. ..=1.).contains(&fbm(p, &o)));
+            assert!((0. ..=1.).contains(&turbulence(p, &o)));
+        }
+        assert!(spread.0 < -0.3 && spread.1 > 0.3, "{spread:?}");
+    }
+
+    #[test]
+    fn worley_distances() {
+        for p in points() {
+            let c = worley(p);
+            assert!(c.f1 <= c.f2);
+            // feature point of the own cell is at most a cell diagonal away
+            assert!(c.f1 <= 3f32.sqrt());
+            assert!((0. ..1.).contains(&c.id));
+        }
+    }
+}
@@ -0,0 +1,18 @@
+use crate::color::Color;
+use crate::shape::Hit;
+use crate::texture::Texture;
+
+/// Color by the material index of the hit, black for indices out of the palette
+#[derive(Debug, Clone, PartialEq, PartialOrd, Default)]
+pub struct Palette {
+    pub colors: Vec<Color>,
+}
+
+impl Texture for Palette {
+    fn sample(&self, hit: &Hit) -> Color {
+        self.colors
+            .get(hit.material)
+            .copied()
+            .unwrap_or(Color::BLACK)
+    }
+}
@@ -0,0 +1,344 @@
+use std::f32::consts::PI;
+
+use crate::color::Color;
+use crate::shape::Hit;
+use crate::texture::noise::{fbm, perlin, turbulence, worley, Octaves};
+use crate::texture::Texture;
+use crate::vec3::Vec3;
+
+/// Coordinates the pattern is evaluated at
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Space {
+    /// Hit point position, makes a solid texture
+    #[default]
+    World,
+    /// Surface texture coordinates as (u, v, 0)
+    Uv,
+}
+
+/// Placement of the pattern on the surface
+/// Point is scaled, rotated around x, y and z axes (in radians) and then offset
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Mapping {
+    pub space: Space,
+    pub scale: Vec3,
+    pub rotation: Vec3,
+    pub offset: Vec3,
+}
+
+impl Default for Mapping {
+    fn default() -> Self {
+        Mapping {
+            space: Space::World,
+            scale: Vec3::diag(1.),
+            rotation: Vec3::zero(),
+            offset: Vec3::zero(),
+        }
+    }
+}
+
+impl Mapping {
+    pub fn point(&self, hit: &Hit) -> Vec3 {
+        let p = match self.space {
+            Space::World => hit.ray.start,
+            Space::Uv => Vec3::new(hit.uv.0, hit.uv.1, 0.),
+        };
+        (p * self.scale)
+            .rotate_x(self.rotation.x)
+            .rotate_y(self.rotation.y)
+            .rotate_z(self.rotation.z)
+            + self.offset
+    }
+}
+
+/// Piecewise linear mapping of [0, 1] pattern values to colors
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct ColorRamp {
+    /// Positions and colors of ramp stops, sorted by position
+    pub stops: Vec<(f32, Color)>,
+}
+
+impl Default for ColorRamp {
+    fn default() -> Self {
+        ColorRamp::new(Color::BLACK, Color::WHITE)
+    }
+}
+
+impl ColorRamp {
+    /// Ramp from `a` at 0 to `b` at 1
+    pub fn new(a: Color, b: Color) -> ColorRamp {
+        ColorRamp {
+            stops: vec![(0., a), (1., b)],
+        }
+    }
+
+    pub fn eval(&self, t: f32) -> Color {
+        let Some(i) = self.stops.iter().position(|(p, _)| *p > t) else {
+            return self.stops.last().map_or(Color::BLACK, |s| s.1);
+        };
+        if i == 0 {
+            return self.stops[0].1;
+        }
+        let ((p0, c0), (p1, c1)) = (self.stops[i - 1], self.stops[i]);
+        c0.lerp(&c1, (t - p0) / (p1 - p0))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub enum Gradient {
+    /// Along the x axis, from 0 to 1
+    Linear,
+    /// By distance from the z axis, from 0 to 1
+    Radial,
+    /// By distance from the origin, from 0 to 1
+    Spherical,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub enum CellFeature {
+    /// Distance to the closest feature point
+    F1,
+    /// Distance to the second closest feature point
+    F2,
+    /// Distance between cell borders, makes cracks
+    F2MinusF1,
+    /// Random value per cell, makes flat colored cells
+    Cell,
+}
+
+/// Scalar pattern with values in [0, 1] range
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub enum Pattern {
+    /// Alternating unit cubes
+    Checker,
+    /// Lines of `width` along integer x and y coordinates, on a background of 0
+    /// Solid grids add lines along integer z, which cover the whole (u, v, 0) plane of surface
+    /// coordinates, so grids in the UV space are not solid
+    Grid {
+        width: f32,
+        solid: bool,
+    },
+    Gradient(Gradient),
+    Perlin,
+    Fractal(Octaves),
+    Turbulence(Octaves),
+    /// Sine stripes along the x axis, distorted by turbulence
+    Marble {
+        stripes: f32,
+        distortion: f32,
+        octaves: Octaves,
+    },
+    /// Concentric rings around the z axis, distorted by noise
+    Wood {
+        rings: f32,
+        distortion: f32,
+    },
+    Voronoi(CellFeature),
+}
+
+impl Pattern {
+    pub fn value(&self, p: Vec3) -> f32 {
+        let v = match self {
+            Pattern::Checker => {
+                let sum = p.x.floor() + p.y.floor() + p.z.floor();
+                sum.rem_euclid(2.)
+            }
+            Pattern::Grid { width, solid } => {
+                let line = |v: f32| (v - v.round()).abs() <= width / 2.;
+                if line(p.x) || line(p.y) || (*solid && line(p.z)) {
+                    1.
+                } else {
+                    0.
+                }
+            }
+            Pattern::Gradient(Gradient::Linear) => p.x,
+            Pattern::Gradient(Gradient::Radial) => (p.x * p.x + p.y * p.y).sqrt(),
+            Pattern::Gradient(Gradient::Spherical) => p.mag(),
+            Pattern::Perlin => perlin(p) * 0.5 + 0.5,
+            Pattern::Fractal(octaves) => fbm(p, octaves) * 0.5 + 0.5,
+            Pattern::Turbulence(octaves) => turbulence(p, octaves),
+            Pattern::Marble {
+                stripes,
+                distortion,
+                octaves,
+            } => {
+                let phase = p.x * stripes + distortion * turbulence(p, octaves);
+                (phase * PI).sin() * 0.5 + 0.5
+            }
+            Pattern::Wood { rings, distortion } => {
+                let r = (p.x * p.x + p.y * p.y).sqrt() * rings + distortion * perlin(p);
+                r.rem_euclid(1.)
+            }
+            Pattern::Voronoi(feature) => {
+                let c = worley(p);
+                match feature {
+                    CellFeature::F1 => c.f1,
+                    CellFeature::F2 => c.f2,
+                    CellFeature::F2MinusF1 => c.f2 - c.f1,
+                    CellFeature::Cell => c.id,
+                }
+            }
+        };
+        v.clamp(0., 1.)
+    }
+}
+
+/// Texture evaluating the pattern at the mapped hit point and coloring it with the ramp
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct Procedural {
+    pub pattern: Pattern,
+    pub mapping: Mapping,
+    pub ramp: ColorRamp,
+}
+
+impl Procedural {
+    pub fn new(pattern: Pattern) -> Procedural {
+        Procedural {
+            pattern,
+            mapping: Mapping::default(),
+            ramp: ColorRamp::default(),
+        }
+    }
+
+    pub fn with_scale(self, scale: f32) -> Procedural {
+        Procedural {
+            mapping: Mapping {
+                scale: Vec3::diag(scale),
+                ..self.mapping
+            },
+            ..self
+        }
+    }
+
+    pub fn with_ramp(self, ramp: ColorRamp) -> Procedural {
+        Procedural { ramp, ..self }
+    }
+}
+
+impl Texture for Procedural {
+    fn sample(&self, hit: &Hit) -> Color {
+        self.ramp.eval(self.pattern.value(self.mapping.point(hit)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f32::consts::PI;
+
+    use crate::color::Color;
+    use crate::math::approx_eq;
+    use crate::ray::Ray;
+    use crate::shape::Hit;
+    use crate::texture::noise::Octaves;
+    use crate::texture::procedural::{CellFeature, ColorRamp, Mapping, Pattern, Procedural, Space};
+    use crate::texture::Texture;
+    use crate::vec3::Vec3;
+
+    fn hit(p: Vec3) -> Hit {
+        Hit {
+            ray: Ray {
+                start: p,
+                dir: Vec3::new(0., 0., 1.),
+            },
+            normal: Vec3::new(0., 0., 1.),
+            uv: (p.x, p.y),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn ramp() {
+        let r = ColorRamp {
+            stops: vec![(0.2, Color::RED), (0.6, Color::BLUE), (1., Color::WHITE)],
+        };
+        assert_eq!(r.eval(0.), Color::RED);
+        let c = r.eval(0.4);
+        assert!(approx_eq(c.r, 0.5) && c.g == 0. && approx_eq(c.b, 0.5));
+        assert_eq!(r.eval(0.6), Color::BLUE);
+        assert_eq!(r.eval(2.), Color::WHITE);
+    }
+
+    #[test]
+    fn checker() {
+        let t = Procedural::new(Pattern::Checker)
+            .with_scale(2.)
+            .with_ramp(ColorRamp::new(Color::RED, Color::GREEN));
+        assert_eq!(t.sample(&hit(Vec3::new(0.1, 0.1, 0.1))), Color::RED);
+        assert_eq!(t.sample(&hit(Vec3::new(0.6, 0.1, 0.1))), Color::GREEN);
+        assert_eq!(t.sample(&hit(Vec3::new(-0.4, 0.1, 0.1))), Color::GREEN);
+        assert_eq!(t.sample(&hit(Vec3::new(0.6, 0.6, 0.1))), Color::RED);
+    }
+
+    #[test]
+    fn mapping() {
+        let m = Mapping {
+            space: Space::Uv,
+            scale: Vec3::new(2., 2., 2.),
+            rotation: Vec3::new(0., 0., PI / 2.),
+            offset: Vec3::new(1., 0., 0.),
+        };
+        let mut h = hit(Vec3::new(5., 5., 5.));
+        h.uv = (1., 0.);
+        assert!(m.point(&h).approx_eq(&Vec3::new(1., 2., 0.)));
+    }
+
+    #[test]
+    fn patterns_in_range() {
+        let patterns = [
+            Pattern::Grid {
+                width: 0.1,
+                solid: true,
+            },
+            Pattern::Perlin,
+            Pattern::Fractal(Octaves::default()),
+            Pattern::Turbulence(Octaves::default()),
+            Pattern::Marble {
+                stripes: 2.,
+                distortion: 3.,
+                octaves: Octaves::default(),
+            },
+            Pattern::Wood {
+                rings: 4.,
+                distortion: 0.5,
+            },
+            Pattern::Voronoi(CellFeature::F1),
+            Pattern::Voronoi(CellFeature::F2MinusF1),
+            Pattern::Voronoi(CellFeature::Cell),
+        ];
+        for pattern in patterns {
+            let values: Vec<f32> = (0..200)
+                .map(|i| pattern.value(Vec3::new(i as f32 * 0.173, i as f32 * 0.029, 0.31)))
+                .collect();
+            assert!(values.iter().all(|v| (0. ..=1.).contains(v)), "{pattern:?}");
+            let mean = values.iter().sum::<f32>() / values.len() as f32;
+            assert!(values.iter().any(|v| !approx_eq(*v, mean)), "{pattern:?}");
+        }
+    }
+
+    #[test]
+    fn grid() {
+        let g = Pattern::Grid {
+            width: 0.1,
+            solid: true,
+        };
+        assert_eq!(g.value(Vec3::new(2.02, 0.5, 0.5)), 1.);
+        assert_eq!(g.value(Vec3::new(2.5, 0.5, 0.5)), 0.);
+        assert_eq!(g.value(Vec3::new(2.5, 0.5, 1.)), 1.);
+
+        let t = Procedural {
+            mapping: Mapping {
+                space: Space::Uv,
+                ..Default::default()
+            },
+            ..Procedural::new(Pattern::Grid {
+                width: 0.1,
+                solid: false,
+            })
+        };
+        let mut h = hit(Vec3::new(3., 3., 3.));
+        h.uv = (0.5, 0.5);
+        assert_eq!(t.sample(&h), Color::BLACK);
+        h.uv = (0.97, 0.5);
+        assert_eq!(t.sample(&h), Color::WHITE);
+    }
+}
@@ -0,0 +1,15 @@
+use crate::color::Color;
+use crate::shape::Hit;
+use crate::texture::Texture;
+
+/// Color interpolated from mesh vertex colors, `fallback` for hits without them
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct VertexColor {
+    pub fallback: Color,
+}
+
+impl Texture for VertexColor {
+    fn sample(&self, hit: &Hit) -> Color {
+        hit.color.unwrap_or(self.fallback)
+    }
+}
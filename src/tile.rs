@@ -0,0 +1,211 @@
+use std::str::FromStr;
+
+/// Rectangular block of image pixels, such as a single unit of render work or a render region
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Tile {
+    pub fn pixel_count(&self) -> usize {
+        self.width * self.height
+    }
+
+    pub fn intersect(&self, other: &Tile) -> Option<Tile> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = (self.x + self.width).min(other.x + other.width);
+        let bottom = (self.y + self.height).min(other.y + other.height);
+        if right <= x || bottom <= y {
+            return None;
+        }
+        Some(Tile {
+            x,
+            y,
+            width: right - x,
+            height: bottom - y,
+        })
+    }
+}
+
+/// Parse from `x,y,width,height`
+impl FromStr for Tile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("invalid rectangle `{s}`, expected `x,y,width,height`");
+        let vs = s
+            .split(',')
+            .map(|v| v.trim().parse().map_err(|_| err()))
+            .collect::<Result<Vec<usize>, _>>()?;
+        match vs[..] {
+            [x, y, width, height] => Ok(Tile {
+                x,
+                y,
+                width,
+                height,
+            }),
+            _ => Err(err()),
+        }
+    }
+}
+
+/// Order in which tiles are handed to render workers
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum TileOrder {
+    /// Row by row, from the top left corner
+    #[default]
+    Scanline,
+    /// From the image center outwards
+    Spiral,
+    /// Along the [Hilbert curve](https://en.wikipedia.org/wiki/Hilbert_curve), keeping
+    /// consecutive tiles close to each other
+    Hilbert,
+}
+
+impl FromStr for TileOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "scanline" => Ok(TileOrder::Scanline),
+            "spiral" => Ok(TileOrder::Spiral),
+            "hilbert" => Ok(TileOrder::Hilbert),
+            _ => Err(format!("unknown tile order `{s}`")),
+        }
+    }
+}
+
+/// Split an image area into tiles of at most `size` by `size` pixels, in the specified order
+pub fn tiles(area: &Tile, size: usize, order: TileOrder) -> Vec<Tile> {
+    let size = size.max(1);
+    let cols = area.width.div_ceil(size);
+    let rows = area.height.div_ceil(size);
+    let mut cells: Vec<(usize, usize)> = (0..rows)
+        .flat_map(|r| (0..cols).map(move |c| (c, r)))
+        .collect();
+    match order {
+        TileOrder::Scanline => {}
+        TileOrder::Spiral => {
+            let center = (cols as f32 / 2. - 0.5, rows as f32 / 2. - 0.5);
+            cells.sort_by(|a, b| spiral_key(*a, center).total_cmp(&spiral_key(*b, center)));
+        }
+        TileOrder::Hilbert => {
+            let n = cols.max(rows).next_power_of_two();
+            cells.sort_by_key(|&(c, r)| hilbert_index(n, c, r));
+        }
+    }
+    cells
+        .into_iter()
+        .map(|(c, r)| Tile {
+            x: area.x + c * size,
+            y: area.y + r * size,
+            width: size.min(area.width - c * size),
+            height: size.min(area.height - r * size),
+        })
+        .collect()
+}
+
+/// Ring index around the center, with the angle within the ring as a fractional part
+fn spiral_key((c, r): (usize, usize), center: (f32, f32)) -> f32 {
+    let dx = c as f32 - center.0;
+    let dy = r as f32 - center.1;
+    let ring = dx.abs().max(dy.abs()).round();
+    let angle = (dy.atan2(dx) + std::f32::consts::PI) / std::f32::consts::TAU;
+    ring + angle.min(0.999)
+}
+
+/// Distance along the Hilbert curve filling an `n` by `n` grid, `n` is a power of two
+/// [ref](https://en.wikipedia.org/wiki/Hilbert_curve#Applications_and_mapping_algorithms)
+fn hilbert_index(n: usize, mut x: usize, mut y: usize) -> usize {
+    let mut d = 0;
+    let mut s = n / 2;
+    while s > 0 {
+        let rx = usize::from(x & s > 0);
+        let ry = usize::from(y & s > 0);
+        d += s * s * ((3 * rx) ^ ry);
+        if ry == 0 {
+            if rx == 1 {
+                x = n - 1 - x;
+                y = n - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    d
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use crate::tile::{hilbert_index, tiles, Tile, TileOrder};
+
+    fn area(x: usize, y: usize, width: usize, height: usize) -> Tile {
+        Tile {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn tiles_cover_image() {
+        for order in [TileOrder::Scanline, TileOrder::Spiral, TileOrder::Hilbert] {
+            let ts = tiles(&area(0, 0, 70, 45), 16, order);
+            assert_eq!(ts.len(), 5 * 3);
+            assert_eq!(ts.iter().map(Tile::pixel_count).sum::<usize>(), 70 * 45);
+            let pixels: HashSet<_> = ts
+                .iter()
+                .flat_map(|t| {
+                    (t.y..t.y + t.height)
+                        .flat_map(move |y| (t.x..t.x + t.width).map(move |x| (x, y)))
+                })
+                .collect();
+            assert_eq!(pixels.len(), 70 * 45);
+        }
+    }
+
+    #[test]
+    fn scanline_order() {
+        let ts = tiles(&area(5, 5, 20, 20), 10, TileOrder::Scanline);
+        let origins: Vec<_> = ts.iter().map(|t| (t.x, t.y)).collect();
+        assert_eq!(origins, vec![(5, 5), (15, 5), (5, 15), (15, 15)]);
+    }
+
+    #[test]
+    fn spiral_starts_in_center() {
+        let ts = tiles(&area(0, 0, 50, 50), 10, TileOrder::Spiral);
+        assert_eq!((ts[0].x, ts[0].y), (20, 20));
+    }
+
+    #[test]
+    fn intersect() {
+        let a = area(0, 0, 10, 10);
+        assert_eq!(a.intersect(&area(5, 8, 10, 10)), Some(area(5, 8, 5, 2)));
+        assert_eq!(a.intersect(&area(10, 0, 10, 10)), None);
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!("1, 2,3,4".parse(), Ok(area(1, 2, 3, 4)));
+        assert!("1,2,3".parse::<Tile>().is_err());
+        assert!("1,2,3,x".parse::<Tile>().is_err());
+    }
+
+    #[test]
+    fn hilbert_adjacent() {
+        let n = 8;
+        let mut cells: Vec<_> = (0..n).flat_map(|y| (0..n).map(move |x| (x, y))).collect();
+        cells.sort_by_key(|&(x, y)| hilbert_index(n, x, y));
+        for w in cells.windows(2) {
+            let d = w[0].0.abs_diff(w[1].0) + w[0].1.abs_diff(w[1].1);
+            assert_eq!(d, 1);
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use std::ops::Mul;
+
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+/// Row-major 4x4 matrix, transforming column vectors
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Matrix4 {
+    pub m: [[f32; 4]; 4],
+}
+
+impl Matrix4 {
+    pub const IDENTITY: Matrix4 = Matrix4 {
+        m: [
+            [1., 0., 0., 0.],
+            [0., 1., 0., 0.],
+            [0., 0., 1., 0.],
+            [0., 0., 0., 1.],
+        ],
+    };
+
+    pub fn transpose(&self) -> Matrix4 {
+        let mut m = [[0.; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v = self.m[j][i];
+            }
+        }
+        Matrix4 { m }
+    }
+
+    /// Inverse by Gauss-Jordan elimination with partial pivoting, `None` for singular matrices
+    pub fn inverse(&self) -> Option<Matrix4> {
+        let mut a = self.m.map(|r| r.map(|v| v as f64));
+        let mut inv = Matrix4::IDENTITY.m.map(|r| r.map(|v| v as f64));
+        for c in 0..4 {
+            let pivot = (c..4).max_by(|i, j| a[*i][c].abs().total_cmp(&a[*j][c].abs()))?;
+            if a[pivot][c].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(c, pivot);
+            inv.swap(c, pivot);
+            let k = a[c][c];
+            for j in 0..4 {
+                a[c][j] /= k;
+                inv[c][j] /= k;
+            }
+            for r in (0..4).filter(|r| *r != c) {
+                let f = a[r][c];
+                for j in 0..4 {
+                    a[r][j] -= f * a[c][j];
+                    inv[r][j] -= f * inv[c][j];
+                }
+            }
+        }
+        Some(Matrix4 {
+            m: inv.map(|r| r.map(|v| v as f32)),
+        })
+    }
+
+    /// Determinant of the upper left 3x3 part, volume scale of the transform
+    pub fn det3(&self) -> f32 {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Matrix4;
+
+    fn mul(self, rhs: Matrix4) -> Matrix4 {
+        let mut m = [[0.; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v = (0..4).map(|k| self.m[i][k] * rhs.m[k][j]).sum();
+            }
+        }
+        Matrix4 { m }
+    }
+}
+
+/// Affine transform along with its inverse
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Transform {
+    pub matrix: Matrix4,
+    pub inverse: Matrix4,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        matrix: Matrix4::IDENTITY,
+        inverse: Matrix4::IDENTITY,
+    };
+
+    /// `None` for singular matrices
+    pub fn from_matrix(matrix: Matrix4) -> Option<Transform> {
+        Some(Transform {
+            matrix,
+            inverse: matrix.inverse()?,
+        })
+    }
+
+    pub fn translate(v: Vec3) -> Transform {
+        let mut t = Transform::IDENTITY;
+        (t.matrix.m[0][3], t.matrix.m[1][3], t.matrix.m[2][3]) = (v.x, v.y, v.z);
+        (t.inverse.m[0][3], t.inverse.m[1][3], t.inverse.m[2][3]) = (-v.x, -v.y, -v.z);
+        t
+    }
+
+    /// Scale along the axes, zero factors are not allowed
+    pub fn scale(v: Vec3) -> Transform {
+        let mut t = Transform::IDENTITY;
+        (t.matrix.m[0][0], t.matrix.m[1][1], t.matrix.m[2][2]) = (v.x, v.y, v.z);
+        (t.inverse.m[0][0], t.inverse.m[1][1], t.inverse.m[2][2]) = (1. / v.x, 1. / v.y, 1. / v.z);
+        t
+    }
+
+    /// Rotation by `angle` in radians around the unit `axis`, counterclockwise when the axis
+    /// points towards the viewer
+    pub fn rotate(axis: Vec3, angle: f32) -> Transform {
+        let (sin, cos) = angle.sin_cos();
+        let (x, y, z) = (axis.x, axis.y, axis.z);
+        let c = 1. - cos;
+        let matrix = Matrix4 {
+            m: [
+                [
+                    cos + x * x * c,
+                    x * y * c - z * sin,
+                    x * z * c + y * sin,
+                    0.,
+                ],
+                [
+                    y * x * c + z * sin,
+                    cos + y * y * c,
+                    y * z * c - x * sin,
+                    0.,
+                ],
+                [
+                    z * x * c - y * sin,
+                    z * y * c + x * sin,
+                    cos + z * z * c,
+                    0.,
+                ],
+                [0., 0., 0., 1.],
+            ],
+        };
+        Transform {
+            matrix,
+            // rotation matrix is orthogonal
+            inverse: matrix.transpose(),
+        }
+    }
+
+    pub fn rotate_x(angle: f32) -> Transform {
+        Transform::rotate(Vec3::new(1., 0., 0.), angle)
+    }
+
+    pub fn rotate_y(angle: f32) -> Transform {
+        Transform::rotate(Vec3::new(0., 1., 0.), angle)
+    }
+
+    pub fn rotate_z(angle: f32) -> Transform {
+        Transform::rotate(Vec3::new(0., 0., 1.), angle)
+    }
+
+    /// Transform applying `self` first and `other` after it
+    pub fn then(&self, other: &Transform) -> Transform {
+        Transform {
+            matrix: other.matrix * self.matrix,
+            inverse: self.inverse * other.inverse,
+        }
+    }
+
+    pub fn inverse(&self) -> Transform {
+        Transform {
+            matrix: self.inverse,
+            inverse: self.matrix,
+        }
+    }
+
+    pub fn point(&self, p: &Vec3) -> Vec3 {
+        let m = &self.matrix.m;
+        self.vector(p) + Vec3::new(m[0][3], m[1][3], m[2][3])
+    }
+
+    pub fn vector(&self, v: &Vec3) -> Vec3 {
+        let m = &self.matrix.m;
+        Vec3::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        )
+    }
+
+    /// Transform the surface normal by the inverse transpose, keeping it perpendicular to the
+    /// transformed surface, result is not normalized
+    pub fn normal(&self, n: &Vec3) -> Vec3 {
+        let m = &self.inverse.m;
+        Vec3::new(
+            m[0][0] * n.x + m[1][0] * n.y + m[2][0] * n.z,
+            m[0][1] * n.x + m[1][1] * n.y + m[2][1] * n.z,
+            m[0][2] * n.x + m[1][2] * n.y + m[2][2] * n.z,
+        )
+    }
+
+    /// Transformed ray, direction is not normalized so that ray parameters are preserved
+    pub fn ray(&self, ray: &Ray) -> Ray {
+        Ray {
+            start: self.point(&ray.start),
+            dir: self.vector(&ray.dir),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f32::consts::PI;
+
+    use crate::math::approx_eq;
+    use crate::transform::{Matrix4, Transform};
+    use crate::vec3::Vec3;
+
+    fn transform() -> Transform {
+        Transform::scale(Vec3::new(2., 1., 0.5))
+            .then(&Transform::rotate(Vec3::new(1., 1., 0.).norm(), 0.7))
+            .then(&Transform::translate(Vec3::new(1., -2., 3.)))
+    }
+
+    #[test]
+    fn compose() {
+        let t = Transform::scale(Vec3::diag(2.)).then(&Transform::translate(Vec3::new(1., 0., 0.)));
+        assert!(t
+            .point(&Vec3::new(1., 1., 1.))
+            .approx_eq(&Vec3::new(3., 2., 2.)));
+        assert!(t
+            .vector(&Vec3::new(1., 1., 1.))
+            .approx_eq(&Vec3::new(2., 2., 2.)));
+    }
+
+    #[test]
+    fn inverse() {
+        let t = transform();
+        let p = Vec3::new(0.3, -4., 2.);
+        assert!(t.inverse().point(&t.point(&p)).approx_eq(&p));
+        let inv = t.matrix.inverse().unwrap();
+        for (a, b) in inv.m.iter().flatten().zip(t.inverse.m.iter().flatten()) {
+            assert!(approx_eq(*a, *b));
+        }
+        let singular = Transform::from_matrix(Matrix4 {
+            m: [
+                [1., 0., 0., 0.],
+                [0., 0., 0., 0.],
+                [0., 0., 1., 0.],
+                [0., 0., 0., 1.],
+            ],
+        });
+        assert!(singular.is_none());
+    }
+
+    #[test]
+    fn rotate() {
+        let v = Vec3::new(1., 2., 3.);
+        assert!(Transform::rotate_z(PI / 3.)
+            .vector(&v)
+            .approx_eq(&v.rotate_z(PI / 3.)));
+        assert!(Transform::rotate_x(1.)
+            .vector(&v)
+            .approx_eq(&v.rotate_x(1.)));
+        assert!(Transform::rotate_y(-2.)
+            .vector(&v)
+            .approx_eq(&v.rotate_y(-2.)));
+    }
+
+    #[test]
+    fn normal() {
+        let t = transform();
+        let (tangent, normal) = (Vec3::new(1., -1., 0.5), Vec3::new(1., 1., 0.));
+        assert!(approx_eq(tangent.dot(&normal), 0.));
+        assert!(approx_eq(t.vector(&tangent).dot(&t.normal(&normal)), 0.));
+    }
+}
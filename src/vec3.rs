@@ -1,6 +1,6 @@
 use std::ops;
 
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use rand_distr::StandardNormal;
 
 use crate::math::approx_eq;
@@ -26,8 +26,7 @@ impl Vec3 {
     }
 
     /// Create a random unit vector in range (-1, 1)
-    pub fn rand() -> Vec3 {
-        let mut rng = thread_rng();
+    pub fn rand(rng: &mut impl Rng) -> Vec3 {
         let x: f32 = rng.sample(StandardNormal);
         let y: f32 = rng.sample(StandardNormal);
         let z: f32 = rng.sample(StandardNormal);